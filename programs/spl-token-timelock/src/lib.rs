@@ -9,6 +9,9 @@
 */
 
 use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+
+use anchor_lang::solana_program::keccak;
 use anchor_lang::solana_program::system_program;
 use anchor_spl::{
     associated_token::{self, AssociatedToken, Create},
@@ -39,6 +42,9 @@ pub mod spl_token_timelock {
         config.payment_vault = ctx.accounts.payment_vault.to_account_info().key();
         config.payment_vault_bump = payment_vault_bump;
         config.authority = *ctx.accounts.authority.key;
+        config.creator = *ctx.accounts.authority.key;
+        config.canceller = *ctx.accounts.authority.key;
+        config.pauser = *ctx.accounts.authority.key;
         config.mint = ctx.accounts.mint.to_account_info().key();
         config.config_bump = config_bump;
 
@@ -50,6 +56,106 @@ pub mod spl_token_timelock {
         Ok(())
     }
 
+    // Migrate the config and payment vault of the mint from the seeds of the config before it was keyed by mint.
+    /**
+     * @param ctx : context of migrate config.
+     * @param config_bump : The PDA bump of the new config account.
+     * @param payment_vault_bump : The PDA bump of the new payment vault token account.
+     */
+    pub fn migrate_config(
+        ctx: Context<MigrateConfig>,
+        config_bump: u8,
+        payment_vault_bump: u8,
+    ) -> ProgramResult {
+        msg!("migrate config");
+
+        let old_config = &ctx.accounts.old_config;
+        let config = &mut ctx.accounts.config;
+        config.payment_vault = ctx.accounts.payment_vault.to_account_info().key();
+        config.payment_vault_bump = payment_vault_bump;
+        config.authority = old_config.authority;
+        config.creator = old_config.authority;
+        config.canceller = old_config.authority;
+        config.pauser = old_config.authority;
+        config.mint = old_config.mint;
+        config.config_bump = config_bump;
+
+        let old_config_key = old_config.key();
+        let seeds = &[old_config_key.as_ref(), &[old_config.payment_vault_bump]];
+        let signer = &[&seeds[..]];
+
+        // Move the tokens of the old payment vault to the new one.
+        let amount = ctx.accounts.old_payment_vault.amount;
+        if amount > 0 {
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.old_payment_vault.to_account_info(),
+                to: ctx.accounts.payment_vault.to_account_info(),
+                authority: ctx.accounts.old_payment_vault.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts).with_signer(signer);
+            token::transfer(cpi_ctx, amount)?;
+        }
+
+        // Close the old payment vault, the old config is closed on exit.
+        let cpi_accounts = CloseAccount {
+            account: ctx.accounts.old_payment_vault.to_account_info(),
+            destination: ctx.accounts.signer.to_account_info(),
+            authority: ctx.accounts.old_payment_vault.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts).with_signer(signer);
+        token::close_account(cpi_ctx)?;
+
+        emit!(MigrateConfigEvent {
+            data: amount,
+            status: "ok".to_string(),
+        });
+
+        Ok(())
+    }
+
+    // Migrate vesting, rewrite a vesting created before the layout changes into the current layout.
+    /**
+     * @param ctx : context of migrate vesting.
+     */
+    pub fn migrate_vesting(ctx: Context<MigrateVesting>) -> ProgramResult {
+        msg!("migrate vesting");
+
+        let vesting = ctx
+            .accounts
+            .vesting
+            .migrate(ctx.accounts.config.payment_vault, ctx.accounts.signer.key());
+        let data = vesting.try_to_vec()?;
+        let space = 8 + data.len();
+
+        // Top up the rent of the grown account.
+        let info = ctx.accounts.vesting.to_account_info();
+        let lamports = ctx.accounts.rent.minimum_balance(space).saturating_sub(info.lamports());
+        if lamports > 0 {
+            solana_program::program::invoke(
+                &solana_program::system_instruction::transfer(ctx.accounts.signer.key, info.key, lamports),
+                &[
+                    ctx.accounts.signer.to_account_info(),
+                    info.clone(),
+                    ctx.accounts.system_program.to_account_info(),
+                ],
+            )?;
+        }
+
+        info.realloc(space, false)?;
+        let mut buf = info.try_borrow_mut_data()?;
+        buf[..8].copy_from_slice(&Vesting::discriminator());
+        buf[8..].copy_from_slice(&data);
+
+        emit!(MigrateVestingEvent {
+            data: vesting.remaining_amount,
+            status: "ok".to_string(),
+        });
+
+        Ok(())
+    }
+
     // Create vesting.
     /**
      * @param ctx : context of create vesting.
@@ -61,12 +167,17 @@ pub mod spl_token_timelock {
      * @param investor_wallet_address : The investor wallet address.
      * @param start_ts : Timestamp when the tokens start vesting.
      * @param end_ts : Timestamp when all tokens are fully vested.
-     * @param period : Time step (period) in seconds per which the vesting occurs.
+     * @param period : Time step (period) per which the vesting occurs, in units of period_unit.
      * @param cliff : Vesting contract "cliff" timestamp.
-     * @param cliff_release_rate : The rate of amount unlocked at the "cliff" timestamp.
-     * @param tge_release_rate : The rate of amount unlocked at TGE.
+     * @param cliff_release_rate : The rate of amount unlocked at the "cliff" timestamp, in basis points.
+     * @param tge_release_rate : The rate of amount unlocked at TGE, in basis points.
      * @param bypass_timestamp_check : Whether to bypass check the timestamp.
+     * @param cancelable_by : Who is allowed to cancel the vesting.
+     * @param withdraw_authority : Who is allowed to withdraw the unlocked tokens.
+     * @param withdraw_delegate : The delegate allowed to withdraw, used by WithdrawAuthority::RecipientOrDelegate.
+     * @param period_unit : The unit of period, seconds or calendar months, quarters and years.
      */
+    #[allow(clippy::too_many_arguments)]
     pub fn create_vesting(
         ctx: Context<CreateVesting>,
         total_amount: u64,
@@ -82,6 +193,10 @@ pub mod spl_token_timelock {
         cliff_release_rate: u64,
         tge_release_rate: u64,
         bypass_timestamp_check: bool,
+        cancelable_by: CancelAuthority,
+        withdraw_authority: WithdrawAuthority,
+        withdraw_delegate: Pubkey,
+        period_unit: PeriodUnit,
     ) -> ProgramResult {
         msg!("create vesting");
 
@@ -98,158 +213,63 @@ pub mod spl_token_timelock {
         msg!("cliff_release_rate: {}", cliff_release_rate);
         msg!("tge_release_rate: {}", tge_release_rate);
 
+        // Check the schedule validity, the timestamps against now unless bypassed.
         let now = ctx.accounts.clock.unix_timestamp as u64;
-        if !bypass_timestamp_check {
-            // Check start,end,cliff timestamp validity.
-            if !time_check(now, start_ts, end_ts, cliff) {
-                emit!(CreateVestingEvent {
-                    data: ErrorCode::InvalidSchedule as u64,
-                    status: "err".to_string(),
-                });
-
-                msg!("time_check failed:");
-                msg!("recipient: {}", ctx.accounts.recipient.key);
-                msg!("now: {}", now);
-                msg!("start_ts: {}", start_ts);
-                msg!("end_ts: {}", end_ts);
-                msg!("cliff: {}", cliff);
-                return Err(ErrorCode::InvalidSchedule.into());
-            }
-        }
-
-        // Check time step period in seconds per validity.
-        if period == 0 || period >= (end_ts - start_ts) {
+        let terms = ScheduleTerms {
+            end_ts,
+            period,
+            cliff,
+            cliff_release_rate,
+            tge_release_rate,
+        };
+        let checked_now = if bypass_timestamp_check { None } else { Some(now) };
+        if let Err(err) =
+            check_schedule(total_amount, start_ts, period_unit, &terms, BPS_DENOMINATOR, checked_now)
+        {
             emit!(CreateVestingEvent {
-                data: ErrorCode::InvalidPeriod as u64,
+                data: err as u64,
                 status: "err".to_string(),
             });
-            msg!("period illegal:");
+            msg!("schedule illegal:");
             msg!("recipient: {}", ctx.accounts.recipient.key);
-            msg!("period: {}", period);
+            msg!("now: {}", now);
             msg!("start_ts: {}", start_ts);
             msg!("end_ts: {}", end_ts);
-            return Err(ErrorCode::InvalidPeriod.into());
-        }
-
-        // Check release rate of tge and cliff validity.
-        if tge_release_rate > 100
-            || cliff_release_rate > 100
-            || tge_release_rate + cliff_release_rate > 100
-        {
-            emit!(CreateVestingEvent {
-                data: ErrorCode::InvalidReleaseRate as u64,
-                status: "err".to_string(),
-            });
-            msg!("tge_release_rate or cliff_release_rate illegal:");
-            msg!("recipient: {}", ctx.accounts.recipient.key);
-            msg!("tge_release_rate: {}", tge_release_rate);
+            msg!("period: {}", period);
+            msg!("cliff: {}", cliff);
             msg!("cliff_release_rate: {}", cliff_release_rate);
-            return Err(ErrorCode::InvalidReleaseRate.into());
-        }
-
-        // Verify that the recipient's associated token address is correct.
-        let recipient_tokens_key = associated_token::get_associated_token_address(
-            ctx.accounts.recipient.key,
-            ctx.accounts.mint.to_account_info().key,
-        );
-        if &recipient_tokens_key != ctx.accounts.recipient_token.key {
-            emit!(CreateVestingEvent {
-                data: ErrorCode::InvalidAssociatedTokenAddress as u64,
-                status: "err".to_string(),
-            });
-            msg!("recipient tokens key not match:");
-            msg!("recipient_tokens_key: {}", recipient_tokens_key);
-            msg!("ctx.accounts.recipient_token.key: {}", *ctx.accounts.recipient_token.key);
-            return Err(ErrorCode::InvalidAssociatedTokenAddress.into());
+            msg!("tge_release_rate: {}", tge_release_rate);
+            return Err(err.into());
         }
 
-        // Check if the recipient's associated token account has been created,
-        // and if not, create an associated token account for the recipient.
-        if ctx.accounts.recipient_token.data_is_empty() {
-            let cpi_accounts = Create {
-                payer: ctx.accounts.signer.to_account_info(),
-                associated_token: ctx.accounts.recipient_token.clone(),
-                authority: ctx.accounts.recipient.to_account_info(),
-                rent: ctx.accounts.rent.to_account_info(),
-                mint: ctx.accounts.mint.to_account_info(),
-                system_program: ctx.accounts.system_program.to_account_info(),
-                token_program: ctx.accounts.token_program.to_account_info(),
-            };
-            let cpi_program = ctx.accounts.associated_token_program.to_account_info();
-            let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
-            associated_token::create(cpi_ctx)?;
-        }
+        // Verify and create the recipient's associated token account if needed.
+        ctx.accounts.funding().create_recipient_token()?;
 
         /*
             Record the relevant status to the vesting account.
         */
+        ctx.accounts.vesting.set_inner(new_vesting(
+            total_amount,
+            escrow_vault_bump,
+            vesting_bump,
+            vesting_id,
+            vesting_name,
+            investor_wallet_address,
+            ctx.accounts.funding().granter(),
+            ctx.accounts.granter_token.key(),
+            ctx.accounts.recipient.key(),
+            ctx.accounts.recipient_token.key(),
+            ctx.accounts.mint.key(),
+            ctx.accounts.escrow_vault.key(),
+            ctx.accounts.signer.key(),
+            now,
+            cancelable_by,
+            withdraw_authority,
+            withdraw_delegate,
+        ));
         let vesting = &mut ctx.accounts.vesting;
-        vesting.magic = 0x544D4C4B;
-        vesting.version = 1;
-        vesting.escrow_vault_bump = escrow_vault_bump;
-        vesting.vesting_bump = vesting_bump;
-        vesting.vesting_id = vesting_id.clone();
-        vesting.vesting_name = vesting_name.clone();
-        vesting.investor_wallet_address = investor_wallet_address.clone();
-
-        vesting.withdrawn_amount = 0;
-        vesting.remaining_amount = total_amount;
-        vesting.total_amount = total_amount;
-
-        vesting.granter = ctx.accounts.payment_vault.to_account_info().key();
-        vesting.granter_token = ctx.accounts.payment_vault.to_account_info().key();
-
-        vesting.recipient = *ctx.accounts.recipient.to_account_info().key;
-        vesting.recipient_token = *ctx.accounts.recipient_token.key;
-        vesting.mint = *ctx.accounts.mint.to_account_info().key;
-        vesting.escrow_vault = *ctx.accounts.escrow_vault.to_account_info().key;
-
-        vesting.created_ts = now;
-        vesting.start_ts = start_ts;
-        vesting.end_ts = end_ts;
-        vesting.accounting_ts = start_ts;
-        vesting.last_withdrawn_at = 0;
-
-        vesting.period = period;
-
-        vesting.cliff = cliff;
-        vesting.cliff_release_rate = cliff_release_rate;
-        vesting.cliff_amount = 0;
-
-        vesting.tge_release_rate = tge_release_rate;
-        vesting.tge_amount = 0;
-
-        // Calculate the cliff amount based on cliff release rate.
-        vesting.cliff_amount = 0;
-
-        // Calculate the tge amount based on tge release rate.
-        if tge_release_rate != 0 {
-            vesting.tge_amount =
-                total_amount.saturating_mul(tge_release_rate) / 100 as u64;
-        }
-
-        // Calculate amount to be unlocked per time during linear unlocking.
-        vesting.periodic_unlock_amount =
-            (((total_amount as f64 - vesting.tge_amount as f64) / (end_ts as f64 - start_ts as f64))
-                * period as f64) as u64;
-
-        // Transfer tokens into the escrow vault.
-        let cpi_accounts = Transfer {
-            from: ctx.accounts.payment_vault.to_account_info(),
-            to: ctx.accounts.escrow_vault.to_account_info(),
-            authority: ctx.accounts.payment_vault.to_account_info(),
-        };
-
-        let config = &ctx.accounts.config;
-
-        let seeds = &[config.to_account_info().key.as_ref(), &[config.payment_vault_bump]];
-        let signer = &[&seeds[..]];
-
-        let cpi_program = ctx.accounts.token_program.to_account_info();
-        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts).with_signer(signer);
-        token::transfer(cpi_ctx, total_amount)?;
+        set_schedule(vesting, total_amount, start_ts, period_unit, &terms);
 
-        msg!("periodic_unlock_amount: {}", vesting.periodic_unlock_amount);
         msg!("vesting.tge_amount: {}", vesting.tge_amount);
         msg!("cliff_amount: {}", vesting.cliff_amount);
         msg!("end_ts: {}", end_ts);
@@ -258,6 +278,9 @@ pub mod spl_token_timelock {
         msg!("cliff: {}", cliff);
         msg!("period: {}", period);
 
+        // Transfer tokens into the escrow vault.
+        ctx.accounts.funding().fund_escrow_vault(total_amount)?;
+
         emit!(CreateVestingEvent {
             data: total_amount,
             status: "ok".to_string(),
@@ -266,508 +289,4526 @@ pub mod spl_token_timelock {
         Ok(())
     }
 
-    // Withdraw.
+    // Create milestone vesting, the tranches unlock when the authority or the oracle releases them.
     /**
-     * @param ctx : context of withdraw.
-     * @param amount : The number of withdraw wanted.
+     * @param ctx : context of create vesting.
+     * @param total_amount : The starting balance of this vesting account, i.e., how much was originally deposited.
+     * @param escrow_vault_bump : The escrow vault bump.
+     * @param vesting_bump : The vesting bump.
+     * @param vesting_id : The vesting id.
+     * @param vesting_name : The vesting name.
+     * @param investor_wallet_address : The investor wallet address.
+     * @param milestone_rates : The rate of each tranche in basis points, they must add up to 10000.
+     * @param milestone_oracle : The account allowed to release milestones besides the authority.
+     * @param cancelable_by : Who is allowed to cancel the vesting.
+     * @param withdraw_authority : Who is allowed to withdraw the unlocked tokens.
+     * @param withdraw_delegate : The delegate allowed to withdraw, used by WithdrawAuthority::RecipientOrDelegate.
      */
-    pub fn withdraw(ctx: Context<Withdraw>, amount: u64) -> ProgramResult {
-        // Check withdrawal amount validity.
-        if amount == 0 {
-            emit!(WithdrawEvent {
-                data: ErrorCode::InvalidWithdrawalAmount as u64,
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_milestone_vesting(
+        ctx: Context<CreateVesting>,
+        total_amount: u64,
+        escrow_vault_bump: u8,
+        vesting_bump: u8,
+        vesting_id: u64,
+        vesting_name: [u8; 32],
+        investor_wallet_address: [u8; 64],
+        milestone_rates: Vec<u64>,
+        milestone_oracle: Pubkey,
+        cancelable_by: CancelAuthority,
+        withdraw_authority: WithdrawAuthority,
+        withdraw_delegate: Pubkey,
+    ) -> ProgramResult {
+        msg!("create milestone vesting");
+
+        msg!("total_amount: {}", total_amount);
+        msg!("vesting_id: {}", vesting_id);
+        msg!("milestone_rates: {:?}", milestone_rates);
+        msg!("milestone_oracle: {}", milestone_oracle);
+
+        // Check deposit amount validity.
+        if total_amount == 0 {
+            emit!(CreateVestingEvent {
+                data: ErrorCode::InvalidDepositAmount as u64,
                 status: "err".to_string(),
             });
-            msg!("withdraw param amount illegal : {}", amount);
-            msg!("recipient_token : {}", *ctx.accounts.recipient_token.to_account_info().key);
-            return Err(ErrorCode::InvalidWithdrawalAmount.into());
+            msg!("total_amount illegal : {}", total_amount);
+            return Err(ErrorCode::InvalidDepositAmount.into());
         }
 
-        let now = ctx.accounts.clock.unix_timestamp as u64;
-        let available = available_for_withdrawal(&ctx.accounts.vesting, now);
-
-        if available == 0 {
-            emit!(WithdrawEvent {
-                data: ErrorCode::InsufficientWithdrawalAmount as u64,
+        // Check the tranches validity.
+        if milestone_rates.is_empty()
+            || milestone_rates.len() > MAX_MILESTONES
+            || milestone_rates.contains(&0)
+            || milestone_rates.iter().try_fold(0u64, |sum, rate| sum.checked_add(*rate))
+                != Some(BPS_DENOMINATOR)
+        {
+            emit!(CreateVestingEvent {
+                data: ErrorCode::InvalidMilestone as u64,
                 status: "err".to_string(),
             });
-            msg!("withdrawal amount illegal : {}", available);
-            msg!("recipient_token : {}", *ctx.accounts.recipient_token.to_account_info().key);
-            return Err(ErrorCode::InsufficientWithdrawalAmount.into());
+            msg!("milestone_rates illegal:");
+            msg!("recipient: {}", ctx.accounts.recipient.key);
+            msg!("milestone_rates: {:?}", milestone_rates);
+            return Err(ErrorCode::InvalidMilestone.into());
         }
 
-        if amount > available {
-            msg!("withdraw param amount is bigger than available :");
-            msg!("recipient_token : {}", *ctx.accounts.recipient_token.to_account_info().key);
-            msg!("amount : {}", amount);
-            msg!("available : {}", available);
-            return Err(ErrorCode::InvalidWithdrawalAmount.into());
-        }
+        // Verify and create the recipient's associated token account if needed.
+        ctx.accounts.funding().create_recipient_token()?;
 
-        // Transfer funds out.
+        /*
+            Record the relevant status to the vesting account.
+        */
+        let now = ctx.accounts.clock.unix_timestamp as u64;
+        ctx.accounts.vesting.set_inner(new_vesting(
+            total_amount,
+            escrow_vault_bump,
+            vesting_bump,
+            vesting_id,
+            vesting_name,
+            investor_wallet_address,
+            ctx.accounts.funding().granter(),
+            ctx.accounts.granter_token.key(),
+            ctx.accounts.recipient.key(),
+            ctx.accounts.recipient_token.key(),
+            ctx.accounts.mint.key(),
+            ctx.accounts.escrow_vault.key(),
+            ctx.accounts.signer.key(),
+            now,
+            cancelable_by,
+            withdraw_authority,
+            withdraw_delegate,
+        ));
         let vesting = &mut ctx.accounts.vesting;
-        let seeds = &[vesting.to_account_info().key.as_ref(), &[vesting.escrow_vault_bump]];
-        let signer = &[&seeds[..]];
-        let cpi_accounts = Transfer {
-            from: ctx.accounts.escrow_vault.to_account_info(),
-            to: ctx.accounts.recipient_token.to_account_info(),
-            authority: ctx.accounts.escrow_vault.to_account_info(),
-        };
-        let cpi_program = ctx.accounts.token_program.to_account_info();
-        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts).with_signer(signer);
-        token::transfer(cpi_ctx, amount)?;
-
-        // Record remaining amount.
-        vesting.remaining_amount = vesting.remaining_amount.checked_sub(amount).unwrap();
-
-        // Record withdrawn amount.
-        vesting.withdrawn_amount = vesting.withdrawn_amount.checked_add(amount).unwrap();
 
-        // Record billing time.
-        vesting.accounting_ts = now
-            - (now - vesting.accounting_ts)
-                .checked_rem(vesting.period)
-                .unwrap();
+        // Split the total amount into tranches, the last one takes the rounding remainder.
+        vesting.kind = VestingKind::Milestone;
+        vesting.milestone_oracle = milestone_oracle;
+        let mut allocated: u64 = 0;
+        for (index, rate) in milestone_rates.iter().enumerate() {
+            let amount = if index + 1 == milestone_rates.len() {
+                total_amount - allocated
+            } else {
+                bps_amount(total_amount, *rate)
+            };
+            allocated += amount;
+            vesting.milestones[index] = Milestone {
+                rate: *rate,
+                amount,
+                released_ts: 0,
+            };
+        }
 
-        // Update last withdrawn timestamp.
-        vesting.last_withdrawn_at = now;
+        // Transfer tokens into the escrow vault.
+        ctx.accounts.funding().fund_escrow_vault(total_amount)?;
 
-        emit!(WithdrawEvent {
-            data: amount,
+        emit!(CreateVestingEvent {
+            data: total_amount,
             status: "ok".to_string(),
         });
 
         Ok(())
     }
 
-    // cancel.
+    // Create piecewise vesting, the unlocked amount follows cumulative rates at breakpoint timestamps.
     /**
-     * @param ctx : context of cancel.
+     * @param ctx : context of create piecewise vesting.
+     * @param total_amount : The starting balance of this vesting account, i.e., how much was originally deposited.
+     * @param escrow_vault_bump : The escrow vault bump.
+     * @param vesting_bump : The vesting bump.
+     * @param vesting_id : The vesting id.
+     * @param vesting_name : The vesting name.
+     * @param investor_wallet_address : The investor wallet address.
+     * @param breakpoints : The (timestamp, cumulative rate in basis points) breakpoints, the last rate must be 10000.
+     * @param interpolation : Whether the rate steps or grows linearly between breakpoints.
+     * @param cancelable_by : Who is allowed to cancel the vesting.
+     * @param withdraw_authority : Who is allowed to withdraw the unlocked tokens.
+     * @param withdraw_delegate : The delegate allowed to withdraw, used by WithdrawAuthority::RecipientOrDelegate.
      */
-    pub fn cancel(ctx: Context<CancelVesting>) -> ProgramResult {
-        //Check the balance in the vault
-        let remaining = ctx.accounts.escrow_vault.amount;
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_piecewise_vesting(
+        ctx: Context<CreatePiecewiseVesting>,
+        total_amount: u64,
+        escrow_vault_bump: u8,
+        vesting_bump: u8,
+        vesting_id: u64,
+        vesting_name: [u8; 32],
+        investor_wallet_address: [u8; 64],
+        breakpoints: Vec<Breakpoint>,
+        interpolation: Interpolation,
+        cancelable_by: CancelAuthority,
+        withdraw_authority: WithdrawAuthority,
+        withdraw_delegate: Pubkey,
+    ) -> ProgramResult {
+        msg!("create piecewise vesting");
 
-        let seeds = &[
-            ctx.accounts.vesting.to_account_info().key.as_ref(),
-            &[ctx.accounts.vesting.escrow_vault_bump],
-        ];
-        let signer = &[&seeds[..]];
+        msg!("total_amount: {}", total_amount);
+        msg!("vesting_id: {}", vesting_id);
+        msg!("breakpoints: {}", breakpoints.len());
 
-        if remaining > 0 {
-            // Transfer funds out.
-            let cpi_accounts = Transfer {
-                from: ctx.accounts.escrow_vault.to_account_info(),
-                to: ctx.accounts.payment_vault.to_account_info(),
-                authority: ctx.accounts.escrow_vault.to_account_info(),
-            };
-            let cpi_program = ctx.accounts.token_program.to_account_info();
-            let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts).with_signer(signer);
-            token::transfer(cpi_ctx, remaining)?;
+        // Check deposit amount validity.
+        if total_amount == 0 {
+            emit!(CreateVestingEvent {
+                data: ErrorCode::InvalidDepositAmount as u64,
+                status: "err".to_string(),
+            });
+            msg!("total_amount illegal : {}", total_amount);
+            return Err(ErrorCode::InvalidDepositAmount.into());
         }
 
-        // Close escrow vault account.
-        let cpi_accounts = CloseAccount {
-            account: ctx.accounts.escrow_vault.to_account_info(),
-            destination: ctx.accounts.payment_vault.to_account_info(),
-            authority: ctx.accounts.escrow_vault.to_account_info(),
-        };
-        let cpi_program = ctx.accounts.token_program.to_account_info();
-        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts).with_signer(signer);
-        token::close_account(cpi_ctx)?;
-
-        emit!(CancelEvent {
-            data: remaining,
-            status: "ok".to_string(),
-        });
+        // Check the breakpoints validity.
+        if !valid_breakpoints(&breakpoints) {
+            emit!(CreateVestingEvent {
+                data: ErrorCode::InvalidBreakpoints as u64,
+                status: "err".to_string(),
+            });
+            msg!("breakpoints illegal:");
+            msg!("recipient: {}", ctx.accounts.recipient.key);
+            for breakpoint in breakpoints.iter() {
+                msg!("ts: {} rate: {}", breakpoint.ts, breakpoint.rate);
+            }
+            return Err(ErrorCode::InvalidBreakpoints.into());
+        }
 
-        Ok(())
-    }
-}
+        // Verify and create the recipient's associated token account if needed.
+        ctx.accounts.funding().create_recipient_token()?;
 
-/// --------------------------------
-/// Context Structs
-/// --------------------------------
+        /*
+            Record the relevant status to the vesting account.
+        */
+        let now = ctx.accounts.clock.unix_timestamp as u64;
+        ctx.accounts.vesting.set_inner(new_vesting(
+            total_amount,
+            escrow_vault_bump,
+            vesting_bump,
+            vesting_id,
+            vesting_name,
+            investor_wallet_address,
+            ctx.accounts.funding().granter(),
+            ctx.accounts.granter_token.key(),
+            ctx.accounts.recipient.key(),
+            ctx.accounts.recipient_token.key(),
+            ctx.accounts.mint.key(),
+            ctx.accounts.escrow_vault.key(),
+            ctx.accounts.signer.key(),
+            now,
+            cancelable_by,
+            withdraw_authority,
+            withdraw_delegate,
+        ));
+        let vesting = &mut ctx.accounts.vesting;
 
-/* initialize context */
-// Accounts for initialize.
-#[derive(Accounts)]
-#[instruction(config_bump: u8, payment_vault_bump: u8)]
-pub struct Initialize<'info> {
+        // The schedule spans from the first to the last breakpoint.
+        vesting.start_ts = breakpoints[0].ts;
+        vesting.end_ts = breakpoints[breakpoints.len() - 1].ts;
 
-    /// The Initializer, the signer and fee payer. & The pubkey of account that have permission to invoke create_vesting and cancel instruction.
-    #[account(mut)]
-    pub signer: Signer<'info>,
+        vesting.kind = VestingKind::Piecewise;
+        vesting.interpolation = interpolation;
+        vesting.breakpoints = breakpoints;
 
-    /// The pubkey of account that have permission to invoke the create_vesting instruction.
-    pub authority: AccountInfo<'info>,
+        // Transfer tokens into the escrow vault.
+        ctx.accounts.funding().fund_escrow_vault(total_amount)?;
 
-    /// Token mint.
-    pub mint: Account<'info, Mint>,
+        emit!(CreateVestingEvent {
+            data: total_amount,
+            status: "ok".to_string(),
+        });
 
-    /// The payment vault token account (PDA).
-    #[account(
-        init, payer = signer,
-        seeds = [config.to_account_info().key.as_ref()], bump = payment_vault_bump,
-        rent_exempt = enforce,
-        token::mint = mint,
-        token::authority = payment_vault,
-    )]
-    pub payment_vault: Account<'info, TokenAccount>,
+        Ok(())
+    }
 
-    /// The account for saving configuration (PDA).
-    #[account(
+    // Create self-funded vesting, any signer locks tokens of its own token account, without the config.
+    /**
+     * @param ctx : context of create self-funded vesting.
+     * @param total_amount : The starting balance of this vesting account, i.e., how much was originally deposited.
+     * @param escrow_vault_bump : The escrow vault bump.
+     * @param vesting_bump : The vesting bump.
+     * @param vesting_id : The vesting id.
+     * @param vesting_name : The vesting name.
+     * @param investor_wallet_address : The investor wallet address.
+     * @param start_ts : Timestamp when the tokens start vesting.
+     * @param end_ts : Timestamp when all tokens are fully vested.
+     * @param period : Time step (period) per which the vesting occurs, in units of period_unit.
+     * @param cliff : Vesting contract "cliff" timestamp.
+     * @param cliff_release_rate : The rate of amount unlocked at the "cliff" timestamp, in basis points.
+     * @param tge_release_rate : The rate of amount unlocked at TGE, in basis points.
+     * @param bypass_timestamp_check : Whether to bypass check the timestamp.
+     * @param cancelable_by : Who is allowed to cancel the vesting, CancelAuthority::Authority stands for the granter.
+     * @param withdraw_authority : Who is allowed to withdraw the unlocked tokens.
+     * @param withdraw_delegate : The delegate allowed to withdraw, used by WithdrawAuthority::RecipientOrDelegate.
+     * @param period_unit : The unit of period, seconds or calendar months, quarters and years.
+     */
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_self_funded_vesting(
+        ctx: Context<CreateSelfFundedVesting>,
+        total_amount: u64,
+        escrow_vault_bump: u8,
+        vesting_bump: u8,
+        vesting_id: u64,
+        vesting_name: [u8; 32],
+        investor_wallet_address: [u8; 64],
+        start_ts: u64,
+        end_ts: u64,
+        period: u64,
+        cliff: u64,
+        cliff_release_rate: u64,
+        tge_release_rate: u64,
+        bypass_timestamp_check: bool,
+        cancelable_by: CancelAuthority,
+        withdraw_authority: WithdrawAuthority,
+        withdraw_delegate: Pubkey,
+        period_unit: PeriodUnit,
+    ) -> ProgramResult {
+        msg!("create self-funded vesting");
+
+        msg!("granter: {}", ctx.accounts.signer.key);
+        msg!("total_amount: {}", total_amount);
+        msg!("vesting_id: {}", vesting_id);
+
+        // Check the amount and schedule, by the same rules as create_vesting.
+        let now = ctx.accounts.clock.unix_timestamp as u64;
+        let terms = ScheduleTerms {
+            end_ts,
+            period,
+            cliff,
+            cliff_release_rate,
+            tge_release_rate,
+        };
+        let checked_now = if bypass_timestamp_check { None } else { Some(now) };
+        if let Err(err) =
+            check_schedule(total_amount, start_ts, period_unit, &terms, BPS_DENOMINATOR, checked_now)
+        {
+            emit!(CreateVestingEvent {
+                data: err as u64,
+                status: "err".to_string(),
+            });
+            msg!("schedule illegal:");
+            msg!("recipient: {}", ctx.accounts.recipient.key);
+            msg!("now: {}", now);
+            msg!("start_ts: {}", start_ts);
+            msg!("end_ts: {}", end_ts);
+            msg!("period: {}", period);
+            msg!("cliff: {}", cliff);
+            msg!("cliff_release_rate: {}", cliff_release_rate);
+            msg!("tge_release_rate: {}", tge_release_rate);
+            return Err(err.into());
+        }
+
+        // Verify and create the recipient's associated token account if needed.
+        if !is_associated_token(
+            ctx.accounts.recipient.key,
+            &ctx.accounts.mint.key(),
+            ctx.accounts.recipient_token.key,
+        ) {
+            emit!(CreateVestingEvent {
+                data: ErrorCode::InvalidAssociatedTokenAddress as u64,
+                status: "err".to_string(),
+            });
+            msg!("recipient tokens key not match:");
+            msg!("ctx.accounts.recipient_token.key: {}", *ctx.accounts.recipient_token.key);
+            return Err(ErrorCode::InvalidAssociatedTokenAddress.into());
+        }
+        create_associated_token_account(
+            ctx.accounts.associated_token_program.to_account_info(),
+            Create {
+                payer: ctx.accounts.signer.to_account_info(),
+                associated_token: ctx.accounts.recipient_token.clone(),
+                authority: ctx.accounts.recipient.to_account_info(),
+                rent: ctx.accounts.rent.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+            },
+        )?;
+
+        /*
+            Record the relevant status to the vesting account, the signer is the granter.
+        */
+        ctx.accounts.vesting.set_inner(Vesting {
+            self_funded: true,
+            ..new_vesting(
+                total_amount,
+                escrow_vault_bump,
+                vesting_bump,
+                vesting_id,
+                vesting_name,
+                investor_wallet_address,
+                ctx.accounts.signer.key(),
+                ctx.accounts.granter_token.key(),
+                ctx.accounts.recipient.key(),
+                ctx.accounts.recipient_token.key(),
+                ctx.accounts.mint.key(),
+                ctx.accounts.escrow_vault.key(),
+                ctx.accounts.signer.key(),
+                now,
+                cancelable_by,
+                withdraw_authority,
+                withdraw_delegate,
+            )
+        });
+        set_schedule(&mut ctx.accounts.vesting, total_amount, start_ts, period_unit, &terms);
+
+        // Transfer tokens from the signer into the escrow vault.
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.granter_token.to_account_info(),
+            to: ctx.accounts.escrow_vault.to_account_info(),
+            authority: ctx.accounts.signer.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+        token::transfer(cpi_ctx, total_amount)?;
+
+        emit!(CreateVestingEvent {
+            data: total_amount,
+            status: "ok".to_string(),
+        });
+
+        Ok(())
+    }
+
+    // Create vestings for many recipients at once, the batch succeeds or fails as a whole.
+    /**
+     * @param ctx : context of create vestings batch, the remaining accounts hold the recipient,
+     * recipient token, vesting and escrow vault accounts of each entry, in order.
+     * @param entries : The recipient, amount and schedule of each vesting.
+     * @param bypass_timestamp_check : Whether to bypass check the timestamp.
+     * @param cancelable_by : Who is allowed to cancel the vestings.
+     * @param withdraw_authority : Who is allowed to withdraw the unlocked tokens.
+     */
+    pub fn create_vestings_batch<'info>(
+        ctx: Context<'_, '_, '_, 'info, CreateVestingsBatch<'info>>,
+        entries: Vec<BatchEntry>,
+        bypass_timestamp_check: bool,
+        cancelable_by: CancelAuthority,
+        withdraw_authority: WithdrawAuthority,
+    ) -> ProgramResult {
+        msg!("create vestings batch");
+
+        msg!("entries: {}", entries.len());
+
+        // Check the batch size and that every entry has its accounts.
+        if entries.is_empty()
+            || entries.len() > MAX_BATCH_ENTRIES
+            || ctx.remaining_accounts.len() != entries.len() * BATCH_ACCOUNTS_PER_ENTRY
+        {
+            emit!(CreateVestingsBatchEvent {
+                data: ErrorCode::InvalidBatch as u64,
+                status: "err".to_string(),
+                count: entries.len() as u64,
+            });
+            msg!("batch illegal:");
+            msg!("entries: {}", entries.len());
+            msg!("remaining_accounts: {}", ctx.remaining_accounts.len());
+            return Err(ErrorCode::InvalidBatch.into());
+        }
+
+        /*
+            Validate every entry before any account is created or any token is transferred.
+        */
+        let now = ctx.accounts.clock.unix_timestamp as u64;
+        let checked_now = if bypass_timestamp_check { None } else { Some(now) };
+        let mint = ctx.accounts.mint.key();
+        let mut total_amount: u64 = 0;
+        let mut vestings: Vec<Pubkey> = Vec::with_capacity(entries.len());
+        for (index, (entry, accounts)) in entries
+            .iter()
+            .zip(ctx.remaining_accounts.chunks(BATCH_ACCOUNTS_PER_ENTRY))
+            .enumerate()
+        {
+            let result = check_schedule(
+                entry.total_amount,
+                entry.start_ts,
+                entry.period_unit,
+                &entry.terms,
+                BPS_DENOMINATOR,
+                checked_now,
+            )
+            .map_err(ProgramError::from)
+            .and_then(|_| check_batch_accounts(ctx.program_id, &mint, entry, accounts))
+                .and_then(|_| {
+                    if vestings.contains(accounts[2].key) {
+                        return Err(ErrorCode::InvalidBatch.into());
+                    }
+                    vestings.push(*accounts[2].key);
+                    total_amount = total_amount
+                        .checked_add(entry.total_amount)
+                        .ok_or(ErrorCode::Overflow)?;
+                    Ok(())
+                });
+            if let Err(err) = result {
+                emit!(CreateVestingsBatchEvent {
+                    data: index as u64,
+                    status: "err".to_string(),
+                    count: entries.len() as u64,
+                });
+                msg!("batch entry illegal:");
+                msg!("index: {}", index);
+                msg!("recipient: {}", entry.recipient);
+                msg!("vesting_id: {}", entry.vesting_id);
+                msg!("total_amount: {}", entry.total_amount);
+                return Err(err);
+            }
+        }
+
+        if total_amount > ctx.accounts.payment_vault.amount {
+            emit!(CreateVestingsBatchEvent {
+                data: ErrorCode::InsufficientPaymentVault as u64,
+                status: "err".to_string(),
+                count: entries.len() as u64,
+            });
+            msg!("batch amount is bigger than balance :");
+            msg!("amount : {}", total_amount);
+            msg!("balance : {}", ctx.accounts.payment_vault.amount);
+            return Err(ErrorCode::InsufficientPaymentVault.into());
+        }
+
+        /*
+            Create and fund the vestings, a failure reverts the whole batch.
+        */
+        for (entry, accounts) in entries
+            .iter()
+            .zip(ctx.remaining_accounts.chunks(BATCH_ACCOUNTS_PER_ENTRY))
+        {
+            ctx.accounts.create_batch_vesting(
+                ctx.program_id,
+                entry,
+                accounts,
+                now,
+                cancelable_by,
+                withdraw_authority,
+            )?;
+        }
+
+        emit!(CreateVestingsBatchEvent {
+            data: total_amount,
+            status: "ok".to_string(),
+            count: entries.len() as u64,
+        });
+
+        Ok(())
+    }
+
+    // Create merkle pool, one escrow funds the vestings of every leaf of the merkle root.
+    /**
+     * @param ctx : context of create merkle pool.
+     * @param merkle_pool_bump : The merkle pool bump.
+     * @param escrow_vault_bump : The escrow vault bump of the pool.
+     * @param pool_id : The merkle pool id.
+     * @param merkle_root : The merkle root of the (index, recipient, amount, schedule) leaves.
+     * @param total_amount : The sum of the amounts of the leaves.
+     * @param claim_deadline : Timestamp after which the authority may claw back the unclaimed tokens.
+     * @param cancelable_by : Who is allowed to cancel the claimed vestings.
+     * @param withdraw_authority : Who is allowed to withdraw the unlocked tokens of the claimed vestings.
+     */
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_merkle_pool(
+        ctx: Context<CreateMerklePool>,
+        merkle_pool_bump: u8,
+        escrow_vault_bump: u8,
+        pool_id: u64,
+        merkle_root: [u8; 32],
+        total_amount: u64,
+        claim_deadline: u64,
+        cancelable_by: CancelAuthority,
+        withdraw_authority: WithdrawAuthority,
+    ) -> ProgramResult {
+        msg!("create merkle pool");
+
+        msg!("pool_id: {}", pool_id);
+        msg!("total_amount: {}", total_amount);
+        msg!("claim_deadline: {}", claim_deadline);
+
+        // Check deposit amount validity.
+        if total_amount == 0 {
+            emit!(CreateMerklePoolEvent {
+                data: ErrorCode::InvalidDepositAmount as u64,
+                status: "err".to_string(),
+            });
+            msg!("total_amount illegal : {}", total_amount);
+            return Err(ErrorCode::InvalidDepositAmount.into());
+        }
+
+        // Check the claim deadline is in the future.
+        let now = ctx.accounts.clock.unix_timestamp as u64;
+        if claim_deadline <= now {
+            emit!(CreateMerklePoolEvent {
+                data: ErrorCode::InvalidTimestamp as u64,
+                status: "err".to_string(),
+            });
+            msg!("claim_deadline illegal:");
+            msg!("now: {}", now);
+            msg!("claim_deadline: {}", claim_deadline);
+            return Err(ErrorCode::InvalidTimestamp.into());
+        }
+
+        let merkle_pool = &mut ctx.accounts.merkle_pool;
+        merkle_pool.magic = 0x544D4C4B;
+        merkle_pool.merkle_pool_bump = merkle_pool_bump;
+        merkle_pool.escrow_vault_bump = escrow_vault_bump;
+        merkle_pool.pool_id = pool_id;
+        merkle_pool.merkle_root = merkle_root;
+        merkle_pool.total_amount = total_amount;
+        merkle_pool.claimed_amount = 0;
+        merkle_pool.mint = ctx.accounts.mint.key();
+        merkle_pool.escrow_vault = ctx.accounts.escrow_vault.key();
+        merkle_pool.created_ts = now;
+        merkle_pool.claim_deadline = claim_deadline;
+        merkle_pool.cancelable_by = cancelable_by;
+        merkle_pool.withdraw_authority = withdraw_authority;
+        merkle_pool.payer = ctx.accounts.signer.key();
+
+        // Transfer tokens into the escrow vault of the pool.
+        transfer_from_payment_vault(
+            &ctx.accounts.config,
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.payment_vault.to_account_info(),
+            ctx.accounts.escrow_vault.to_account_info(),
+            total_amount,
+        )?;
+
+        emit!(CreateMerklePoolEvent {
+            data: total_amount,
+            status: "ok".to_string(),
+        });
+
+        Ok(())
+    }
+
+    // Claim merkle vesting, the recipient proves the leaf and pays the rent of the vesting.
+    /**
+     * @param ctx : context of claim merkle vesting.
+     * @param vesting_bump : The vesting bump.
+     * @param escrow_vault_bump : The escrow vault bump.
+     * @param claim_receipt_bump : The claim receipt bump.
+     * @param leaf : The leaf of the recipient.
+     * @param proof : The sibling hashes from the leaf up to the merkle root.
+     */
+    pub fn claim_merkle_vesting(
+        ctx: Context<ClaimMerkleVesting>,
+        vesting_bump: u8,
+        escrow_vault_bump: u8,
+        claim_receipt_bump: u8,
+        leaf: MerkleLeaf,
+        proof: Vec<[u8; 32]>,
+    ) -> ProgramResult {
+        msg!("claim merkle vesting");
+
+        msg!("index: {}", leaf.index);
+        msg!("recipient: {}", leaf.recipient);
+        msg!("total_amount: {}", leaf.total_amount);
+
+        // Check the claim deadline.
+        let now = ctx.accounts.clock.unix_timestamp as u64;
+        if now > ctx.accounts.merkle_pool.claim_deadline {
+            emit!(ClaimMerkleVestingEvent {
+                data: ErrorCode::ClaimDeadlinePassed as u64,
+                status: "err".to_string(),
+            });
+            msg!("claim deadline passed:");
+            msg!("now: {}", now);
+            msg!("claim_deadline: {}", ctx.accounts.merkle_pool.claim_deadline);
+            return Err(ErrorCode::ClaimDeadlinePassed.into());
+        }
+
+        // Check the leaf belongs to the merkle root.
+        if !verify_merkle_proof(&proof, &ctx.accounts.merkle_pool.merkle_root, &merkle_leaf(&leaf)) {
+            emit!(ClaimMerkleVestingEvent {
+                data: ErrorCode::InvalidMerkleProof as u64,
+                status: "err".to_string(),
+            });
+            msg!("merkle proof illegal:");
+            msg!("proof: {}", proof.len());
+            return Err(ErrorCode::InvalidMerkleProof.into());
+        }
+
+        // Check the schedule of the leaf, it may start before the claim.
+        if let Err(err) = check_schedule(
+            leaf.total_amount,
+            leaf.start_ts,
+            leaf.period_unit,
+            &leaf.terms,
+            BPS_DENOMINATOR,
+            None,
+        ) {
+            emit!(ClaimMerkleVestingEvent {
+                data: err as u64,
+                status: "err".to_string(),
+            });
+            msg!("leaf schedule illegal:");
+            msg!("start_ts: {}", leaf.start_ts);
+            msg!("end_ts: {}", leaf.terms.end_ts);
+            msg!("period: {}", leaf.terms.period);
+            msg!("cliff: {}", leaf.terms.cliff);
+            return Err(err.into());
+        }
+
+        // Check the pool holds the unclaimed amount of the leaf.
+        let merkle_pool = &mut ctx.accounts.merkle_pool;
+        let claimed_amount = merkle_pool
+            .claimed_amount
+            .checked_add(leaf.total_amount)
+            .ok_or(ErrorCode::Overflow)?;
+        if claimed_amount > merkle_pool.total_amount {
+            emit!(ClaimMerkleVestingEvent {
+                data: ErrorCode::MerklePoolExhausted as u64,
+                status: "err".to_string(),
+            });
+            msg!("merkle pool exhausted:");
+            msg!("claimed_amount: {}", merkle_pool.claimed_amount);
+            msg!("total_amount: {}", merkle_pool.total_amount);
+            return Err(ErrorCode::MerklePoolExhausted.into());
+        }
+        merkle_pool.claimed_amount = claimed_amount;
+
+        // Record the claim of the leaf, the receipt outlives the vesting so the leaf is claimed once.
+        let claim_receipt = &mut ctx.accounts.claim_receipt;
+        claim_receipt.magic = 0x544D4C4B;
+        claim_receipt.claim_receipt_bump = claim_receipt_bump;
+        claim_receipt.merkle_pool = ctx.accounts.merkle_pool.key();
+        claim_receipt.index = leaf.index;
+        claim_receipt.vesting = ctx.accounts.vesting.key();
+        claim_receipt.claimed_ts = now;
+
+        // Verify and create the recipient's associated token account if needed.
+        if !is_associated_token(
+            ctx.accounts.recipient.key,
+            &ctx.accounts.mint.key(),
+            ctx.accounts.recipient_token.key,
+        ) {
+            emit!(ClaimMerkleVestingEvent {
+                data: ErrorCode::InvalidAssociatedTokenAddress as u64,
+                status: "err".to_string(),
+            });
+            msg!("recipient tokens key not match:");
+            msg!("ctx.accounts.recipient_token.key: {}", *ctx.accounts.recipient_token.key);
+            return Err(ErrorCode::InvalidAssociatedTokenAddress.into());
+        }
+        create_associated_token_account(
+            ctx.accounts.associated_token_program.to_account_info(),
+            Create {
+                payer: ctx.accounts.recipient.to_account_info(),
+                associated_token: ctx.accounts.recipient_token.clone(),
+                authority: ctx.accounts.recipient.to_account_info(),
+                rent: ctx.accounts.rent.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+            },
+        )?;
+
+        /*
+            Record the relevant status to the vesting account, as if the payment vault created it.
+        */
+        ctx.accounts.vesting.set_inner(new_vesting(
+            leaf.total_amount,
+            escrow_vault_bump,
+            vesting_bump,
+            leaf.index,
+            [0; 32],
+            [0; 64],
+            ctx.accounts.config.payment_vault,
+            ctx.accounts.config.payment_vault,
+            leaf.recipient,
+            *ctx.accounts.recipient_token.key,
+            ctx.accounts.mint.key(),
+            ctx.accounts.escrow_vault.key(),
+            ctx.accounts.recipient.key(),
+            now,
+            ctx.accounts.merkle_pool.cancelable_by,
+            ctx.accounts.merkle_pool.withdraw_authority,
+            Pubkey::default(),
+        ));
+        set_schedule(
+            &mut ctx.accounts.vesting,
+            leaf.total_amount,
+            leaf.start_ts,
+            leaf.period_unit,
+            &leaf.terms,
+        );
+
+        // Transfer tokens from the escrow vault of the pool into the escrow vault of the vesting.
+        let merkle_pool_key = ctx.accounts.merkle_pool.key();
+        let seeds = &[
+            merkle_pool_key.as_ref(),
+            &[ctx.accounts.merkle_pool.escrow_vault_bump],
+        ];
+        let signer = &[&seeds[..]];
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.pool_escrow_vault.to_account_info(),
+            to: ctx.accounts.escrow_vault.to_account_info(),
+            authority: ctx.accounts.pool_escrow_vault.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts).with_signer(signer);
+        token::transfer(cpi_ctx, leaf.total_amount)?;
+
+        emit!(ClaimMerkleVestingEvent {
+            data: leaf.total_amount,
+            status: "ok".to_string(),
+        });
+
+        Ok(())
+    }
+
+    // Claw back the unclaimed tokens of a merkle pool after the claim deadline, and close the pool.
+    /**
+     * @param ctx : context of clawback merkle pool.
+     */
+    pub fn clawback_merkle_pool(ctx: Context<ClawbackMerklePool>) -> ProgramResult {
+        let now = ctx.accounts.clock.unix_timestamp as u64;
+        if now <= ctx.accounts.merkle_pool.claim_deadline {
+            emit!(ClawbackMerklePoolEvent {
+                data: ErrorCode::ClaimDeadlineNotReached as u64,
+                status: "err".to_string(),
+            });
+            msg!("claim deadline not reached:");
+            msg!("now: {}", now);
+            msg!("claim_deadline: {}", ctx.accounts.merkle_pool.claim_deadline);
+            return Err(ErrorCode::ClaimDeadlineNotReached.into());
+        }
+
+        let merkle_pool_key = ctx.accounts.merkle_pool.key();
+        let seeds = &[
+            merkle_pool_key.as_ref(),
+            &[ctx.accounts.merkle_pool.escrow_vault_bump],
+        ];
+        let signer = &[&seeds[..]];
+
+        // Transfer the unclaimed tokens back to the payment vault.
+        let unclaimed_amount = ctx.accounts.escrow_vault.amount;
+        if unclaimed_amount != 0 {
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.escrow_vault.to_account_info(),
+                to: ctx.accounts.payment_vault.to_account_info(),
+                authority: ctx.accounts.escrow_vault.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts).with_signer(signer);
+            token::transfer(cpi_ctx, unclaimed_amount)?;
+        }
+
+        // Close escrow vault account.
+        let cpi_accounts = CloseAccount {
+            account: ctx.accounts.escrow_vault.to_account_info(),
+            destination: ctx.accounts.payer.to_account_info(),
+            authority: ctx.accounts.escrow_vault.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts).with_signer(signer);
+        token::close_account(cpi_ctx)?;
+
+        emit!(ClawbackMerklePoolEvent {
+            data: unclaimed_amount,
+            status: "ok".to_string(),
+        });
+
+        Ok(())
+    }
+
+    // Release a milestone tranche, only the authority or the milestone oracle can release.
+    /**
+     * @param ctx : context of release milestone.
+     * @param index : The index of the tranche to release.
+     */
+    pub fn release_milestone(ctx: Context<ReleaseMilestone>, index: u8) -> ProgramResult {
+        let now = ctx.accounts.clock.unix_timestamp as u64;
+        let vesting = &mut ctx.accounts.vesting;
+
+        let index = index as usize;
+        if vesting.kind != VestingKind::Milestone
+            || index >= MAX_MILESTONES
+            || vesting.milestones[index].rate == 0
+        {
+            emit!(ReleaseMilestoneEvent {
+                data: ErrorCode::InvalidMilestone as u64,
+                status: "err".to_string(),
+                index: index as u8,
+            });
+            msg!("milestone index illegal : {}", index);
+            return Err(ErrorCode::InvalidMilestone.into());
+        }
+
+        if vesting.milestones[index].released_ts != 0 {
+            emit!(ReleaseMilestoneEvent {
+                data: ErrorCode::MilestoneAlreadyReleased as u64,
+                status: "err".to_string(),
+                index: index as u8,
+            });
+            msg!("milestone already released : {}", index);
+            return Err(ErrorCode::MilestoneAlreadyReleased.into());
+        }
+
+        vesting.milestones[index].released_ts = now;
+
+        emit!(ReleaseMilestoneEvent {
+            data: vesting.milestones[index].amount,
+            status: "ok".to_string(),
+            index: index as u8,
+        });
+
+        Ok(())
+    }
+
+    // Top up a vesting from the payment vault, only the authority can top up.
+    /**
+     * @param ctx : context of top up vesting.
+     * @param amount : The number of tokens to add to the vesting.
+     * @param release_immediately : Whether the tokens are released at once, otherwise they unlock pro-rata over the remaining periods.
+     */
+    pub fn top_up_vesting(
+        ctx: Context<TopUpVesting>,
+        amount: u64,
+        release_immediately: bool,
+    ) -> ProgramResult {
+        // Check deposit amount validity.
+        if amount == 0 {
+            emit!(TopUpVestingEvent {
+                data: ErrorCode::InvalidDepositAmount as u64,
+                status: "err".to_string(),
+                release_immediately,
+            });
+            msg!("top up param amount illegal : {}", amount);
+            return Err(ErrorCode::InvalidDepositAmount.into());
+        }
+
+        // Only the linear schedule has remaining periods to spread the tokens over.
+        if !release_immediately && ctx.accounts.vesting.kind != VestingKind::Schedule {
+            emit!(TopUpVestingEvent {
+                data: ErrorCode::InvalidVestingKind as u64,
+                status: "err".to_string(),
+                release_immediately,
+            });
+            msg!("pro-rata top up needs a linear schedule");
+            return Err(ErrorCode::InvalidVestingKind.into());
+        }
+
+        let now = ctx.accounts.clock.unix_timestamp as u64;
+        top_up(&mut ctx.accounts.vesting, amount, release_immediately, now)?;
+
+        // Transfer tokens into the escrow vault.
+        transfer_from_payment_vault(
+            &ctx.accounts.config,
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.payment_vault.to_account_info(),
+            ctx.accounts.escrow_vault.to_account_info(),
+            amount,
+        )?;
+
+        emit!(TopUpVestingEvent {
+            data: amount,
+            status: "ok".to_string(),
+            release_immediately,
+        });
+
+        Ok(())
+    }
+
+    // Amend the schedule of a vesting, both the authority and the recipient must sign.
+    /**
+     * @param ctx : context of amend schedule.
+     * @param terms : The new end, period, cliff and release rates of the schedule.
+     */
+    pub fn amend_schedule(ctx: Context<AmendSchedule>, terms: ScheduleTerms) -> ProgramResult {
+        if ctx.accounts.vesting.kind != VestingKind::Schedule {
+            emit!(AmendScheduleEvent {
+                data: ErrorCode::InvalidVestingKind as u64,
+                status: "err".to_string(),
+                old_terms: ScheduleTerms::of(&ctx.accounts.vesting),
+                new_terms: terms,
+            });
+            msg!("only a linear schedule can be amended");
+            return Err(ErrorCode::InvalidVestingKind.into());
+        }
+
+        let now = ctx.accounts.clock.unix_timestamp as u64;
+        let old_terms = ScheduleTerms::of(&ctx.accounts.vesting);
+        if let Err(err) = amend(&mut ctx.accounts.vesting, &terms, now) {
+            emit!(AmendScheduleEvent {
+                data: err as u64,
+                status: "err".to_string(),
+                old_terms,
+                new_terms: terms,
+            });
+            msg!("amend schedule failed:");
+            msg!("end_ts: {}", terms.end_ts);
+            msg!("period: {}", terms.period);
+            msg!("cliff: {}", terms.cliff);
+            msg!("cliff_release_rate: {}", terms.cliff_release_rate);
+            msg!("tge_release_rate: {}", terms.tge_release_rate);
+            return Err(err.into());
+        }
+
+        emit!(AmendScheduleEvent {
+            data: 0,
+            status: "ok".to_string(),
+            old_terms,
+            new_terms: terms,
+        });
+
+        Ok(())
+    }
+
+    // Withdraw, the recipient may send the tokens to any token account of the mint.
+    /**
+     * @param ctx : context of withdraw.
+     * @param amount : The number of withdraw wanted, u64::MAX withdraws everything available.
+     */
+    pub fn withdraw(ctx: Context<Withdraw>, amount: u64) -> ProgramResult {
+        let now = ctx.accounts.clock.unix_timestamp as u64;
+        withdraw_from_escrow(
+            &mut ctx.accounts.vesting,
+            &ctx.accounts.escrow_vault,
+            &ctx.accounts.recipient_token,
+            ctx.accounts.token_program.to_account_info(),
+            amount,
+            now,
+        )
+    }
+
+    // Withdraw all, the amount available at execution time is reported in the event.
+    /**
+     * @param ctx : context of withdraw.
+     */
+    pub fn withdraw_all(ctx: Context<Withdraw>) -> ProgramResult {
+        withdraw(ctx, u64::MAX)
+    }
+
+    // cancel, settle the vested tokens to the recipient and return the rest to the granter token account.
+    /**
+     * @param ctx : context of cancel.
+     */
+    pub fn cancel(ctx: Context<CancelVesting>) -> ProgramResult {
+        let now = ctx.accounts.clock.unix_timestamp as u64;
+        cancel_to_granter(
+            &ctx.accounts.vesting,
+            &ctx.accounts.escrow_vault,
+            &ctx.accounts.recipient_token,
+            &ctx.accounts.granter_token,
+            &ctx.accounts.payer,
+            ctx.accounts.token_program.to_account_info(),
+            now,
+        )
+    }
+
+    // Withdraw from a self-funded vesting, the config is not involved.
+    /**
+     * @param ctx : context of withdraw self-funded.
+     * @param amount : The number of withdraw wanted, u64::MAX withdraws everything available.
+     */
+    pub fn withdraw_self_funded(ctx: Context<WithdrawSelfFunded>, amount: u64) -> ProgramResult {
+        let now = ctx.accounts.clock.unix_timestamp as u64;
+        withdraw_from_escrow(
+            &mut ctx.accounts.vesting,
+            &ctx.accounts.escrow_vault,
+            &ctx.accounts.recipient_token,
+            ctx.accounts.token_program.to_account_info(),
+            amount,
+            now,
+        )
+    }
+
+    // Cancel a self-funded vesting, the unvested tokens go back to the granter token account.
+    /**
+     * @param ctx : context of cancel self-funded.
+     */
+    pub fn cancel_self_funded(ctx: Context<CancelSelfFunded>) -> ProgramResult {
+        let now = ctx.accounts.clock.unix_timestamp as u64;
+        cancel_to_granter(
+            &ctx.accounts.vesting,
+            &ctx.accounts.escrow_vault,
+            &ctx.accounts.recipient_token,
+            &ctx.accounts.granter_token,
+            &ctx.accounts.payer,
+            ctx.accounts.token_program.to_account_info(),
+            now,
+        )
+    }
+
+    // Close a fully withdrawn vesting, the rent goes back to the payer of the creation.
+    /**
+     * @param ctx : context of close vesting.
+     */
+    pub fn close_vesting(ctx: Context<CloseVesting>) -> ProgramResult {
+        let seeds = &[
+            ctx.accounts.vesting.to_account_info().key.as_ref(),
+            &[ctx.accounts.vesting.escrow_vault_bump],
+        ];
+        let signer = &[&seeds[..]];
+
+        // Close escrow vault account.
+        let cpi_accounts = CloseAccount {
+            account: ctx.accounts.escrow_vault.to_account_info(),
+            destination: ctx.accounts.payer.to_account_info(),
+            authority: ctx.accounts.escrow_vault.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts).with_signer(signer);
+        token::close_account(cpi_ctx)?;
+
+        emit!(CloseVestingEvent {
+            data: ctx.accounts.vesting.withdrawn_amount,
+            status: "ok".to_string(),
+        });
+
+        Ok(())
+    }
+
+    // Transfer a vesting position to a new recipient, signed by the current recipient.
+    // The withdraw delegate acted for the previous recipient, so it is dropped.
+    /**
+     * @param ctx : context of transfer vesting.
+     */
+    pub fn transfer_vesting(ctx: Context<TransferVesting>) -> ProgramResult {
+        transfer_to_new_recipient(
+            &mut ctx.accounts.vesting,
+            ctx.accounts.associated_token_program.to_account_info(),
+            Create {
+                payer: ctx.accounts.signer.to_account_info(),
+                associated_token: ctx.accounts.new_recipient_token.clone(),
+                authority: ctx.accounts.new_recipient.to_account_info(),
+                rent: ctx.accounts.rent.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+            },
+        )
+    }
+
+    // Transfer a self-funded vesting to a new recipient, the config is not involved.
+    /**
+     * @param ctx : context of transfer vesting self-funded.
+     */
+    pub fn transfer_vesting_self_funded(ctx: Context<TransferVestingSelfFunded>) -> ProgramResult {
+        transfer_to_new_recipient(
+            &mut ctx.accounts.vesting,
+            ctx.accounts.associated_token_program.to_account_info(),
+            Create {
+                payer: ctx.accounts.signer.to_account_info(),
+                associated_token: ctx.accounts.new_recipient_token.clone(),
+                authority: ctx.accounts.new_recipient.to_account_info(),
+                rent: ctx.accounts.rent.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+            },
+        )
+    }
+
+    // Fund payment vault.
+    /**
+     * @param ctx : context of fund payment vault.
+     * @param amount : The number of tokens to deposit into the payment vault.
+     */
+    pub fn fund_payment_vault(ctx: Context<FundPaymentVault>, amount: u64) -> ProgramResult {
+        // Check deposit amount validity.
+        if amount == 0 {
+            emit!(FundPaymentVaultEvent {
+                data: ErrorCode::InvalidDepositAmount as u64,
+                status: "err".to_string(),
+            });
+            msg!("fund param amount illegal : {}", amount);
+            return Err(ErrorCode::InvalidDepositAmount.into());
+        }
+
+        // Transfer tokens into the payment vault.
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.source_token.to_account_info(),
+            to: ctx.accounts.payment_vault.to_account_info(),
+            authority: ctx.accounts.signer.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+        token::transfer(cpi_ctx, amount)?;
+
+        emit!(FundPaymentVaultEvent {
+            data: amount,
+            status: "ok".to_string(),
+        });
+
+        Ok(())
+    }
+
+    // Withdraw payment vault.
+    /**
+     * @param ctx : context of withdraw payment vault.
+     * @param amount : The number of tokens to withdraw from the payment vault.
+     */
+    pub fn withdraw_payment_vault(ctx: Context<WithdrawPaymentVault>, amount: u64) -> ProgramResult {
+        // Check withdrawal amount validity.
+        if amount == 0 {
+            emit!(WithdrawPaymentVaultEvent {
+                data: ErrorCode::InvalidWithdrawalAmount as u64,
+                status: "err".to_string(),
+            });
+            msg!("withdraw param amount illegal : {}", amount);
+            return Err(ErrorCode::InvalidWithdrawalAmount.into());
+        }
+
+        if amount > ctx.accounts.payment_vault.amount {
+            emit!(WithdrawPaymentVaultEvent {
+                data: ErrorCode::InsufficientWithdrawalBalance as u64,
+                status: "err".to_string(),
+            });
+            msg!("withdraw param amount is bigger than balance :");
+            msg!("amount : {}", amount);
+            msg!("balance : {}", ctx.accounts.payment_vault.amount);
+            return Err(ErrorCode::InsufficientWithdrawalBalance.into());
+        }
+
+        // Transfer tokens out of the payment vault.
+        let config = &ctx.accounts.config;
+        let seeds = &[config.to_account_info().key.as_ref(), &[config.payment_vault_bump]];
+        let signer = &[&seeds[..]];
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.payment_vault.to_account_info(),
+            to: ctx.accounts.destination_token.to_account_info(),
+            authority: ctx.accounts.payment_vault.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts).with_signer(signer);
+        token::transfer(cpi_ctx, amount)?;
+
+        emit!(WithdrawPaymentVaultEvent {
+            data: amount,
+            status: "ok".to_string(),
+        });
+
+        Ok(())
+    }
+
+    // Propose a new authority, the handover completes when the new authority accepts it.
+    /**
+     * @param ctx : context of propose authority.
+     * @param new_authority : The pubkey of the proposed authority, the default pubkey revokes a pending proposal.
+     */
+    pub fn propose_authority(ctx: Context<ProposeAuthority>, new_authority: Pubkey) -> ProgramResult {
+        let config = &mut ctx.accounts.config;
+        config.pending_authority = new_authority;
+
+        emit!(ProposeAuthorityEvent {
+            data: 0,
+            status: "ok".to_string(),
+            authority: config.authority,
+            pending_authority: new_authority,
+        });
+
+        Ok(())
+    }
+
+    // Accept the pending authority proposal.
+    /**
+     * @param ctx : context of accept authority.
+     */
+    pub fn accept_authority(ctx: Context<AcceptAuthority>) -> ProgramResult {
+        let config = &mut ctx.accounts.config;
+        let previous_authority = config.authority;
+        config.authority = config.pending_authority;
+        config.pending_authority = Pubkey::default();
+
+        emit!(AcceptAuthorityEvent {
+            data: 0,
+            status: "ok".to_string(),
+            previous_authority,
+            authority: config.authority,
+        });
+
+        Ok(())
+    }
+
+    // Set roles, only the admin can update the roles.
+    /**
+     * @param ctx : context of set roles.
+     * @param creator : The pubkey of account that have permission to invoke create_vesting.
+     * @param canceller : The pubkey of account that have permission to cancel vestings.
+     * @param pauser : The pubkey of account that have permission to pause and unpause.
+     */
+    pub fn set_roles(
+        ctx: Context<SetRoles>,
+        creator: Pubkey,
+        canceller: Pubkey,
+        pauser: Pubkey,
+    ) -> ProgramResult {
+        let config = &mut ctx.accounts.config;
+        config.creator = creator;
+        config.canceller = canceller;
+        config.pauser = pauser;
+
+        emit!(SetRolesEvent {
+            data: 0,
+            status: "ok".to_string(),
+            creator,
+            canceller,
+            pauser,
+        });
+
+        Ok(())
+    }
+
+    // Set whether transfer_vesting needs the approval of the authority.
+    /**
+     * @param ctx : context of set transfer approval.
+     * @param required : Whether the authority must co-sign transfer_vesting.
+     */
+    pub fn set_transfer_approval(ctx: Context<SetTransferApproval>, required: bool) -> ProgramResult {
+        ctx.accounts.config.transfer_requires_approval = required;
+
+        emit!(SetTransferApprovalEvent {
+            data: 0,
+            status: "ok".to_string(),
+            required,
+        });
+
+        Ok(())
+    }
+
+    // Pause create_vesting and withdraw.
+    /**
+     * @param ctx : context of pause.
+     */
+    pub fn pause(ctx: Context<SetPaused>) -> ProgramResult {
+        ctx.accounts.config.paused = true;
+
+        emit!(PauseEvent {
+            data: 0,
+            status: "ok".to_string(),
+            paused: true,
+        });
+
+        Ok(())
+    }
+
+    // Unpause create_vesting and withdraw.
+    /**
+     * @param ctx : context of unpause.
+     */
+    pub fn unpause(ctx: Context<SetPaused>) -> ProgramResult {
+        ctx.accounts.config.paused = false;
+
+        emit!(PauseEvent {
+            data: 0,
+            status: "ok".to_string(),
+            paused: false,
+        });
+
+        Ok(())
+    }
+}
+
+/// --------------------------------
+/// Context Structs
+/// --------------------------------
+
+/* initialize context */
+// Accounts for initialize.
+#[derive(Accounts)]
+#[instruction(config_bump: u8, payment_vault_bump: u8)]
+pub struct Initialize<'info> {
+
+    /// The Initializer, the signer and fee payer.
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    /// The pubkey of the admin account, it initially holds every role.
+    pub authority: AccountInfo<'info>,
+
+    /// Token mint.
+    pub mint: Account<'info, Mint>,
+
+    /// The payment vault token account (PDA).
+    #[account(
+        init, payer = signer,
+        seeds = [config.to_account_info().key.as_ref()], bump = payment_vault_bump,
+        rent_exempt = enforce,
+        token::mint = mint,
+        token::authority = payment_vault,
+    )]
+    pub payment_vault: Account<'info, TokenAccount>,
+
+    /// The account for saving configuration (PDA), one per mint.
+    #[account(
+        init, payer = signer,
+        seeds = [b"gyc_timelock".as_ref(), mint.key().as_ref()],
+        bump = config_bump,
+        owner = id(),
+        rent_exempt = enforce,
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    /// Token program.
+    #[account(address = token::ID)]
+    pub token_program: Program<'info, Token>,
+
+    /// Associated token program.
+    #[account(address = associated_token::ID)]
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    /// System program.
+    #[account(address = system_program::ID)]
+    pub system_program: Program<'info, System>,
+
+    ///Rent for rent exempt.
+    #[account(address = solana_program::sysvar::rent::ID)]
+    pub rent: Sysvar<'info, Rent>,
+}
+
+/* migrate_config context */
+// Accounts for migrate_config.
+#[derive(Accounts)]
+#[instruction(config_bump: u8, payment_vault_bump: u8)]
+pub struct MigrateConfig<'info> {
+
+    /// The authority of the old config, the signer and fee payer.
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    /// Token mint.
+    pub mint: Account<'info, Mint>,
+
+    /// The payment vault token account of the old config, closed by the migration.
+    #[account(
+        mut,
+        seeds = [old_config.to_account_info().key.as_ref()], bump = old_config.payment_vault_bump,
+        constraint = old_payment_vault.mint == mint.key() @ErrorCode::InvalidMintMismatch,
+    )]
+    pub old_payment_vault: Account<'info, TokenAccount>,
+
+    /// The config account before it was keyed by mint (PDA), closed by the migration.
+    #[account(
+        mut,
+        close = signer,
+        seeds = [b"gyc_timelock".as_ref()],
+        bump = old_config.config_bump,
+        owner = id(),
+        constraint = old_config.authority == signer.key() @ErrorCode::Unauthorized,
+        constraint = old_config.mint == mint.key() @ErrorCode::InvalidMintMismatch,
+        constraint = old_config.payment_vault == old_payment_vault.key() @ErrorCode::InvalidTokenVaultMismatch,
+    )]
+    pub old_config: Box<Account<'info, ConfigV1>>,
+
+    /// The payment vault token account (PDA).
+    #[account(
+        init, payer = signer,
+        seeds = [config.to_account_info().key.as_ref()], bump = payment_vault_bump,
+        rent_exempt = enforce,
+        token::mint = mint,
+        token::authority = payment_vault,
+    )]
+    pub payment_vault: Account<'info, TokenAccount>,
+
+    /// The account for saving configuration (PDA), one per mint.
+    #[account(
+        init, payer = signer,
+        seeds = [b"gyc_timelock".as_ref(), mint.key().as_ref()],
+        bump = config_bump,
+        owner = id(),
+        rent_exempt = enforce,
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    /// Token program.
+    #[account(address = token::ID)]
+    pub token_program: Program<'info, Token>,
+
+    /// System program.
+    #[account(address = system_program::ID)]
+    pub system_program: Program<'info, System>,
+
+    ///Rent for rent exempt.
+    #[account(address = solana_program::sysvar::rent::ID)]
+    pub rent: Sysvar<'info, Rent>,
+}
+
+/* migrate_vesting context */
+// Accounts for migrate_vesting.
+#[derive(Accounts)]
+pub struct MigrateVesting<'info> {
+
+    /// The config authority, paying the rent of the grown vesting account.
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    /// The account for saving configuration (PDA).
+    #[account(
+        seeds = [b"gyc_timelock".as_ref(), vesting.mint.as_ref()],
+        bump = config.config_bump,
+        owner = id(),
+        constraint = config.authority == signer.key() @ErrorCode::Unauthorized,
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    /// vesting in the layout of the first version.
+    #[account(
+        mut,
+        owner = id() @ErrorCode::InvalidVestingOwner,
+        constraint = vesting.magic == 0x544D4C4B @ErrorCode::InvalidMagic,
+        constraint = vesting.to_account_info().data_len() == 8 + VESTING_V1_SIZE @ErrorCode::InvalidVestingLayout,
+    )]
+    pub vesting: Box<Account<'info, VestingV1>>,
+
+    /// System program.
+    #[account(address = system_program::ID)]
+    pub system_program: Program<'info, System>,
+
+    ///Rent for rent exempt.
+    #[account(address = solana_program::sysvar::rent::ID)]
+    pub rent: Sysvar<'info, Rent>,
+}
+
+/* create_vesting context */
+// Accounts for create_vesting.
+#[derive(Accounts)]
+#[instruction(total_amount: u64, escrow_vault_bump: u8, vesting_bump: u8, vesting_id: u64)]
+pub struct CreateVesting<'info> {
+
+    /// The account that must have permission to invoke this instruction.
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    /// The payment vault token account.
+    #[account(
+        mut,
+        seeds = [config.to_account_info().key.as_ref()], bump = config.payment_vault_bump,
+        constraint = payment_vault.mint == config.mint @ErrorCode::InvalidMintMismatch,
+    )]
+    pub payment_vault: Account<'info, TokenAccount>,
+
+    /// The token account funding the vesting, the payment vault or a token account of the signer.
+    #[account(
+        mut,
+        constraint = granter_token.mint == mint.key() @ErrorCode::InvalidMintMismatch,
+    )]
+    pub granter_token: Account<'info, TokenAccount>,
+
+    /// The account for saving configuration (PDA).
+    #[account(
+        seeds = [b"gyc_timelock".as_ref(), mint.key().as_ref()],
+        bump = config.config_bump,
+        owner = id(),
+        constraint = config.creator == signer.key() @ErrorCode::Unauthorized,
+        constraint = config.mint == mint.key() @ErrorCode::InvalidMintMismatch,
+        constraint = !config.paused @ErrorCode::Paused,
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    /// the recipient of main account
+    pub recipient: AccountInfo<'info>,
+    /// the recipient of token account
+    #[account(mut)]
+    pub recipient_token: AccountInfo<'info>,
+
+    /// vesting account, keyed by vesting id, recipient and mint.
+    #[account(
+        init,
+        payer = signer,
+        seeds = [vesting_id.to_string().as_ref(), recipient.key().as_ref(), mint.key().as_ref()], bump = vesting_bump,
+        owner = id(),
+        rent_exempt = enforce,
+    )]
+    pub vesting: Box<Account<'info, Vesting>>,
+
+    /// escrow vault.
+    #[account(
+        init, payer = signer,
+        seeds = [vesting.to_account_info().key.as_ref()], bump = escrow_vault_bump,
+        owner = token_program.key(),
+        rent_exempt = enforce,
+        token::mint = mint,
+        token::authority = escrow_vault,
+    )]
+    pub escrow_vault: Account<'info, TokenAccount>,
+
+    /// Token mint.
+    pub mint: Account<'info, Mint>,
+
+    /// Token program.
+    #[account(address = token::ID)]
+    pub token_program: Program<'info, Token>,
+
+    /// Associated token program.
+    #[account(address = associated_token::ID)]
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    /// System program.
+    #[account(address = system_program::ID)]
+    pub system_program: Program<'info, System>,
+
+    /// Clock represents network time.
+    #[account(address = solana_program::sysvar::clock::ID)]
+    pub clock: Sysvar<'info, Clock>,
+
+    ///Rent for rent exempt.
+    #[account(address = solana_program::sysvar::rent::ID)]
+    pub rent: Sysvar<'info, Rent>,
+}
+
+impl<'info> CreateVesting<'info> {
+    /// The accounts funding the vesting.
+    fn funding(&self) -> VestingFunding<'_, 'info> {
+        VestingFunding {
+            signer: &self.signer,
+            payment_vault: &self.payment_vault,
+            granter_token: &self.granter_token,
+            config: &self.config,
+            recipient: &self.recipient,
+            recipient_token: &self.recipient_token,
+            escrow_vault: &self.escrow_vault,
+            mint: &self.mint,
+            token_program: &self.token_program,
+            associated_token_program: &self.associated_token_program,
+            system_program: &self.system_program,
+            rent: &self.rent,
+        }
+    }
+}
+
+/// The accounts funding a vesting, shared by create_vesting and create_piecewise_vesting.
+struct VestingFunding<'a, 'info> {
+    signer: &'a Signer<'info>,
+    payment_vault: &'a Account<'info, TokenAccount>,
+    granter_token: &'a Account<'info, TokenAccount>,
+    config: &'a Account<'info, Config>,
+    recipient: &'a AccountInfo<'info>,
+    recipient_token: &'a AccountInfo<'info>,
+    escrow_vault: &'a Account<'info, TokenAccount>,
+    mint: &'a Account<'info, Mint>,
+    token_program: &'a Program<'info, Token>,
+    associated_token_program: &'a Program<'info, AssociatedToken>,
+    system_program: &'a Program<'info, System>,
+    rent: &'a Sysvar<'info, Rent>,
+}
+
+impl<'a, 'info> VestingFunding<'a, 'info> {
+    /// Verify the recipient's associated token address, and create the account if it does not exist yet.
+    fn create_recipient_token(&self) -> ProgramResult {
+        if !is_associated_token(self.recipient.key, &self.mint.key(), self.recipient_token.key) {
+            emit!(CreateVestingEvent {
+                data: ErrorCode::InvalidAssociatedTokenAddress as u64,
+                status: "err".to_string(),
+            });
+            msg!("recipient tokens key not match:");
+            msg!("ctx.accounts.recipient_token.key: {}", *self.recipient_token.key);
+            return Err(ErrorCode::InvalidAssociatedTokenAddress.into());
+        }
+
+        create_associated_token_account(
+            self.associated_token_program.to_account_info(),
+            Create {
+                payer: self.signer.to_account_info(),
+                associated_token: self.recipient_token.clone(),
+                authority: self.recipient.to_account_info(),
+                rent: self.rent.to_account_info(),
+                mint: self.mint.to_account_info(),
+                system_program: self.system_program.to_account_info(),
+                token_program: self.token_program.to_account_info(),
+            },
+        )
+    }
+
+    /// The granter main account, the payment vault or the signer funding from its own token account.
+    fn granter(&self) -> Pubkey {
+        if self.granter_token.key() == self.payment_vault.key() {
+            self.payment_vault.key()
+        } else {
+            self.signer.key()
+        }
+    }
+
+    /// Transfer `amount` tokens from the granter token account into the escrow vault, signed by the
+    /// payment vault PDA or by the signer.
+    fn fund_escrow_vault(&self, amount: u64) -> ProgramResult {
+        if self.granter_token.key() == self.payment_vault.key() {
+            return transfer_from_payment_vault(
+                self.config,
+                self.token_program.to_account_info(),
+                self.payment_vault.to_account_info(),
+                self.escrow_vault.to_account_info(),
+                amount,
+            );
+        }
+
+        let cpi_accounts = Transfer {
+            from: self.granter_token.to_account_info(),
+            to: self.escrow_vault.to_account_info(),
+            authority: self.signer.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), cpi_accounts);
+        token::transfer(cpi_ctx, amount)
+    }
+}
+
+/* create_piecewise_vesting context */
+// Accounts for create_piecewise_vesting, the vesting account is sized by the breakpoints.
+#[derive(Accounts)]
+#[instruction(
+    total_amount: u64,
+    escrow_vault_bump: u8,
+    vesting_bump: u8,
+    vesting_id: u64,
+    vesting_name: [u8; 32],
+    investor_wallet_address: [u8; 64],
+    breakpoints: Vec<Breakpoint>,
+)]
+pub struct CreatePiecewiseVesting<'info> {
+
+    /// The account that must have permission to invoke this instruction.
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    /// The payment vault token account.
+    #[account(
+        mut,
+        seeds = [config.to_account_info().key.as_ref()], bump = config.payment_vault_bump,
+        constraint = payment_vault.mint == config.mint @ErrorCode::InvalidMintMismatch,
+    )]
+    pub payment_vault: Account<'info, TokenAccount>,
+
+    /// The token account funding the vesting, the payment vault or a token account of the signer.
+    #[account(
+        mut,
+        constraint = granter_token.mint == mint.key() @ErrorCode::InvalidMintMismatch,
+    )]
+    pub granter_token: Account<'info, TokenAccount>,
+
+    /// The account for saving configuration (PDA).
+    #[account(
+        seeds = [b"gyc_timelock".as_ref(), mint.key().as_ref()],
+        bump = config.config_bump,
+        owner = id(),
+        constraint = config.creator == signer.key() @ErrorCode::Unauthorized,
+        constraint = config.mint == mint.key() @ErrorCode::InvalidMintMismatch,
+        constraint = !config.paused @ErrorCode::Paused,
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    /// the recipient of main account
+    pub recipient: AccountInfo<'info>,
+    /// the recipient of token account
+    #[account(mut)]
+    pub recipient_token: AccountInfo<'info>,
+
+    /// vesting account, keyed by vesting id, recipient and mint.
+    #[account(
+        init,
+        payer = signer,
+        space = 8 + Vesting::default().try_to_vec().unwrap().len() + breakpoints.len() * BREAKPOINT_SIZE,
+        seeds = [vesting_id.to_string().as_ref(), recipient.key().as_ref(), mint.key().as_ref()], bump = vesting_bump,
+        owner = id(),
+        rent_exempt = enforce,
+    )]
+    pub vesting: Box<Account<'info, Vesting>>,
+
+    /// escrow vault.
+    #[account(
+        init, payer = signer,
+        seeds = [vesting.to_account_info().key.as_ref()], bump = escrow_vault_bump,
+        owner = token_program.key(),
+        rent_exempt = enforce,
+        token::mint = mint,
+        token::authority = escrow_vault,
+    )]
+    pub escrow_vault: Account<'info, TokenAccount>,
+
+    /// Token mint.
+    pub mint: Account<'info, Mint>,
+
+    /// Token program.
+    #[account(address = token::ID)]
+    pub token_program: Program<'info, Token>,
+
+    /// Associated token program.
+    #[account(address = associated_token::ID)]
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    /// System program.
+    #[account(address = system_program::ID)]
+    pub system_program: Program<'info, System>,
+
+    /// Clock represents network time.
+    #[account(address = solana_program::sysvar::clock::ID)]
+    pub clock: Sysvar<'info, Clock>,
+
+    ///Rent for rent exempt.
+    #[account(address = solana_program::sysvar::rent::ID)]
+    pub rent: Sysvar<'info, Rent>,
+}
+
+impl<'info> CreatePiecewiseVesting<'info> {
+    /// The accounts funding the vesting.
+    fn funding(&self) -> VestingFunding<'_, 'info> {
+        VestingFunding {
+            signer: &self.signer,
+            payment_vault: &self.payment_vault,
+            granter_token: &self.granter_token,
+            config: &self.config,
+            recipient: &self.recipient,
+            recipient_token: &self.recipient_token,
+            escrow_vault: &self.escrow_vault,
+            mint: &self.mint,
+            token_program: &self.token_program,
+            associated_token_program: &self.associated_token_program,
+            system_program: &self.system_program,
+            rent: &self.rent,
+        }
+    }
+}
+
+/* create_self_funded_vesting context */
+// Accounts for create_self_funded_vesting, the config is not involved.
+#[derive(Accounts)]
+#[instruction(total_amount: u64, escrow_vault_bump: u8, vesting_bump: u8, vesting_id: u64)]
+pub struct CreateSelfFundedVesting<'info> {
+
+    /// The granter, any account funding the vesting from its own token account.
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    /// The token account of the signer funding the vesting.
+    #[account(
+        mut,
+        constraint = granter_token.mint == mint.key() @ErrorCode::InvalidMintMismatch,
+    )]
+    pub granter_token: Account<'info, TokenAccount>,
+
+    /// the recipient of main account
+    pub recipient: AccountInfo<'info>,
+    /// the recipient of token account
+    #[account(mut)]
+    pub recipient_token: AccountInfo<'info>,
+
+    /// vesting account, keyed by vesting id, recipient, mint and granter.
+    #[account(
+        init,
+        payer = signer,
+        seeds = [vesting_id.to_string().as_ref(), recipient.key().as_ref(), mint.key().as_ref(), signer.key().as_ref()], bump = vesting_bump,
+        owner = id(),
+        rent_exempt = enforce,
+    )]
+    pub vesting: Box<Account<'info, Vesting>>,
+
+    /// escrow vault.
+    #[account(
         init, payer = signer,
-        seeds = [b"gyc_timelock".as_ref()],
-        bump = config_bump,
+        seeds = [vesting.to_account_info().key.as_ref()], bump = escrow_vault_bump,
+        owner = token_program.key(),
+        rent_exempt = enforce,
+        token::mint = mint,
+        token::authority = escrow_vault,
+    )]
+    pub escrow_vault: Account<'info, TokenAccount>,
+
+    /// Token mint.
+    pub mint: Account<'info, Mint>,
+
+    /// Token program.
+    #[account(address = token::ID)]
+    pub token_program: Program<'info, Token>,
+
+    /// Associated token program.
+    #[account(address = associated_token::ID)]
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    /// System program.
+    #[account(address = system_program::ID)]
+    pub system_program: Program<'info, System>,
+
+    /// Clock represents network time.
+    #[account(address = solana_program::sysvar::clock::ID)]
+    pub clock: Sysvar<'info, Clock>,
+
+    ///Rent for rent exempt.
+    #[account(address = solana_program::sysvar::rent::ID)]
+    pub rent: Sysvar<'info, Rent>,
+}
+
+/* create_vestings_batch context */
+// Accounts for create_vestings_batch, the accounts of each entry follow as remaining accounts.
+#[derive(Accounts)]
+pub struct CreateVestingsBatch<'info> {
+
+    /// The account that must have permission to invoke this instruction.
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    /// The payment vault token account.
+    #[account(
+        mut,
+        seeds = [config.to_account_info().key.as_ref()], bump = config.payment_vault_bump,
+        constraint = payment_vault.mint == config.mint @ErrorCode::InvalidMintMismatch,
+    )]
+    pub payment_vault: Account<'info, TokenAccount>,
+
+    /// The account for saving configuration (PDA).
+    #[account(
+        seeds = [b"gyc_timelock".as_ref(), mint.key().as_ref()],
+        bump = config.config_bump,
+        owner = id(),
+        constraint = config.creator == signer.key() @ErrorCode::Unauthorized,
+        constraint = config.mint == mint.key() @ErrorCode::InvalidMintMismatch,
+        constraint = !config.paused @ErrorCode::Paused,
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    /// Token mint.
+    pub mint: Account<'info, Mint>,
+
+    /// Token program.
+    #[account(address = token::ID)]
+    pub token_program: Program<'info, Token>,
+
+    /// Associated token program.
+    #[account(address = associated_token::ID)]
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    /// System program.
+    #[account(address = system_program::ID)]
+    pub system_program: Program<'info, System>,
+
+    /// Clock represents network time.
+    #[account(address = solana_program::sysvar::clock::ID)]
+    pub clock: Sysvar<'info, Clock>,
+
+    ///Rent for rent exempt.
+    #[account(address = solana_program::sysvar::rent::ID)]
+    pub rent: Sysvar<'info, Rent>,
+}
+
+impl<'info> CreateVestingsBatch<'info> {
+    /// Create, record and fund the vesting of a validated batch entry.
+    fn create_batch_vesting(
+        &self,
+        program_id: &Pubkey,
+        entry: &BatchEntry,
+        accounts: &[AccountInfo<'info>],
+        now: u64,
+        cancelable_by: CancelAuthority,
+        withdraw_authority: WithdrawAuthority,
+    ) -> ProgramResult {
+        let (recipient, recipient_token, vesting, escrow_vault) =
+            (&accounts[0], &accounts[1], &accounts[2], &accounts[3]);
+
+        // Verify and create the recipient's associated token account if needed.
+        create_associated_token_account(
+            self.associated_token_program.to_account_info(),
+            Create {
+                payer: self.signer.to_account_info(),
+                associated_token: recipient_token.clone(),
+                authority: recipient.clone(),
+                rent: self.rent.to_account_info(),
+                mint: self.mint.to_account_info(),
+                system_program: self.system_program.to_account_info(),
+                token_program: self.token_program.to_account_info(),
+            },
+        )?;
+
+        // Create the vesting account, keyed by vesting id, recipient and mint.
+        let vesting_id = entry.vesting_id.to_string();
+        let mint = self.mint.key();
+        self.create_pda_account(
+            vesting,
+            8 + Vesting::default().try_to_vec().unwrap().len(),
+            program_id,
+            &[
+                vesting_id.as_bytes(),
+                entry.recipient.as_ref(),
+                mint.as_ref(),
+                &[entry.vesting_bump],
+            ],
+        )?;
+
+        // Create the escrow vault, the token account is its own authority.
+        self.create_pda_account(
+            escrow_vault,
+            TokenAccount::LEN,
+            &token::ID,
+            &[vesting.key.as_ref(), &[entry.escrow_vault_bump]],
+        )?;
+        let cpi_accounts = token::InitializeAccount {
+            account: escrow_vault.clone(),
+            mint: self.mint.to_account_info(),
+            authority: escrow_vault.clone(),
+            rent: self.rent.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), cpi_accounts);
+        token::initialize_account(cpi_ctx)?;
+
+        /*
+            Record the relevant status to the vesting account.
+        */
+        let mut state = new_vesting(
+            entry.total_amount,
+            entry.escrow_vault_bump,
+            entry.vesting_bump,
+            entry.vesting_id,
+            [0; 32],
+            [0; 64],
+            self.payment_vault.key(),
+            self.payment_vault.key(),
+            entry.recipient,
+            *recipient_token.key,
+            mint,
+            *escrow_vault.key,
+            self.signer.key(),
+            now,
+            cancelable_by,
+            withdraw_authority,
+            Pubkey::default(),
+        );
+        set_schedule(&mut state, entry.total_amount, entry.start_ts, entry.period_unit, &entry.terms);
+        {
+            let mut data = vesting.try_borrow_mut_data()?;
+            let dst: &mut [u8] = &mut data;
+            let mut cursor = std::io::Cursor::new(dst);
+            state.try_serialize(&mut cursor)?;
+        }
+
+        // Transfer tokens into the escrow vault.
+        transfer_from_payment_vault(
+            &self.config,
+            self.token_program.to_account_info(),
+            self.payment_vault.to_account_info(),
+            escrow_vault.clone(),
+            entry.total_amount,
+        )
+    }
+
+    /// Create a rent exempt account of `space` bytes owned by `owner` at the PDA of `seeds`.
+    /// The PDA may already hold lamports, sent by anyone, as create_account would then fail.
+    fn create_pda_account(
+        &self,
+        account: &AccountInfo<'info>,
+        space: usize,
+        owner: &Pubkey,
+        seeds: &[&[u8]],
+    ) -> ProgramResult {
+        let lamports = self.rent.minimum_balance(space);
+        if account.lamports() == 0 {
+            let ix = solana_program::system_instruction::create_account(
+                self.signer.key,
+                account.key,
+                lamports,
+                space as u64,
+                owner,
+            );
+            return solana_program::program::invoke_signed(
+                &ix,
+                &[
+                    self.signer.to_account_info(),
+                    account.clone(),
+                    self.system_program.to_account_info(),
+                ],
+                &[seeds],
+            );
+        }
+
+        // Top up to rent exemption, then allocate and assign the account in place.
+        let top_up = lamports.saturating_sub(account.lamports());
+        if top_up != 0 {
+            let ix = solana_program::system_instruction::transfer(self.signer.key, account.key, top_up);
+            solana_program::program::invoke(
+                &ix,
+                &[
+                    self.signer.to_account_info(),
+                    account.clone(),
+                    self.system_program.to_account_info(),
+                ],
+            )?;
+        }
+
+        let ix = solana_program::system_instruction::allocate(account.key, space as u64);
+        solana_program::program::invoke_signed(
+            &ix,
+            &[account.clone(), self.system_program.to_account_info()],
+            &[seeds],
+        )?;
+
+        let ix = solana_program::system_instruction::assign(account.key, owner);
+        solana_program::program::invoke_signed(
+            &ix,
+            &[account.clone(), self.system_program.to_account_info()],
+            &[seeds],
+        )
+    }
+}
+
+/* create_merkle_pool context */
+// Accounts for create_merkle_pool.
+#[derive(Accounts)]
+#[instruction(merkle_pool_bump: u8, escrow_vault_bump: u8, pool_id: u64)]
+pub struct CreateMerklePool<'info> {
+
+    /// The account that must have permission to invoke this instruction.
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    /// The payment vault token account.
+    #[account(
+        mut,
+        seeds = [config.to_account_info().key.as_ref()], bump = config.payment_vault_bump,
+        constraint = payment_vault.mint == config.mint @ErrorCode::InvalidMintMismatch,
+    )]
+    pub payment_vault: Account<'info, TokenAccount>,
+
+    /// The account for saving configuration (PDA).
+    #[account(
+        seeds = [b"gyc_timelock".as_ref(), mint.key().as_ref()],
+        bump = config.config_bump,
+        owner = id(),
+        constraint = config.creator == signer.key() @ErrorCode::Unauthorized,
+        constraint = config.mint == mint.key() @ErrorCode::InvalidMintMismatch,
+        constraint = !config.paused @ErrorCode::Paused,
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    /// merkle pool account, keyed by pool id and mint.
+    #[account(
+        init,
+        payer = signer,
+        seeds = [b"merkle_pool".as_ref(), pool_id.to_string().as_ref(), mint.key().as_ref()], bump = merkle_pool_bump,
+        owner = id(),
+        rent_exempt = enforce,
+    )]
+    pub merkle_pool: Box<Account<'info, MerklePool>>,
+
+    /// escrow vault of the pool, holding the unclaimed tokens.
+    #[account(
+        init, payer = signer,
+        seeds = [merkle_pool.to_account_info().key.as_ref()], bump = escrow_vault_bump,
+        owner = token_program.key(),
+        rent_exempt = enforce,
+        token::mint = mint,
+        token::authority = escrow_vault,
+    )]
+    pub escrow_vault: Account<'info, TokenAccount>,
+
+    /// Token mint.
+    pub mint: Account<'info, Mint>,
+
+    /// Token program.
+    #[account(address = token::ID)]
+    pub token_program: Program<'info, Token>,
+
+    /// System program.
+    #[account(address = system_program::ID)]
+    pub system_program: Program<'info, System>,
+
+    /// Clock represents network time.
+    #[account(address = solana_program::sysvar::clock::ID)]
+    pub clock: Sysvar<'info, Clock>,
+
+    ///Rent for rent exempt.
+    #[account(address = solana_program::sysvar::rent::ID)]
+    pub rent: Sysvar<'info, Rent>,
+}
+
+/* claim_merkle_vesting context */
+// Accounts for claim_merkle_vesting.
+#[derive(Accounts)]
+#[instruction(vesting_bump: u8, escrow_vault_bump: u8, claim_receipt_bump: u8, leaf: MerkleLeaf)]
+pub struct ClaimMerkleVesting<'info> {
+    /// The recipient of the leaf, it pays the rent of the vesting.
+    #[account(
+        mut,
+        constraint = recipient.key() == leaf.recipient @ErrorCode::Unauthorized,
+    )]
+    pub recipient: Signer<'info>,
+
+    /// the recipient of token account
+    #[account(mut)]
+    pub recipient_token: AccountInfo<'info>,
+
+    /// The account for saving configuration (PDA).
+    #[account(
+        seeds = [b"gyc_timelock".as_ref(), mint.key().as_ref()],
+        bump = config.config_bump,
+        owner = id(),
+        constraint = !config.paused @ErrorCode::Paused,
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    /// merkle pool account.
+    #[account(
+        mut,
+        owner = id(),
+        constraint = merkle_pool.magic == 0x544D4C4B @ErrorCode::InvalidMagic,
+        constraint = merkle_pool.mint == mint.key() @ErrorCode::InvalidMintMismatch,
+        constraint = merkle_pool.escrow_vault == pool_escrow_vault.key() @ErrorCode::InvalidEscrowVaultMismatch,
+    )]
+    pub merkle_pool: Box<Account<'info, MerklePool>>,
+
+    /// escrow vault of the pool.
+    #[account(
+        mut,
+        seeds = [merkle_pool.to_account_info().key.as_ref()],
+        bump = merkle_pool.escrow_vault_bump,
+    )]
+    pub pool_escrow_vault: Account<'info, TokenAccount>,
+
+    /// claim receipt account, keyed by pool and leaf index and never closed, so each leaf is claimed once.
+    #[account(
+        init,
+        payer = recipient,
+        seeds = [b"claim_receipt".as_ref(), merkle_pool.to_account_info().key.as_ref(), leaf.index.to_string().as_ref()], bump = claim_receipt_bump,
+        owner = id(),
+        rent_exempt = enforce,
+    )]
+    pub claim_receipt: Account<'info, ClaimReceipt>,
+
+    /// vesting account, keyed by pool and leaf index.
+    #[account(
+        init,
+        payer = recipient,
+        seeds = [merkle_pool.to_account_info().key.as_ref(), leaf.index.to_string().as_ref()], bump = vesting_bump,
+        owner = id(),
+        rent_exempt = enforce,
+    )]
+    pub vesting: Box<Account<'info, Vesting>>,
+
+    /// escrow vault.
+    #[account(
+        init, payer = recipient,
+        seeds = [vesting.to_account_info().key.as_ref()], bump = escrow_vault_bump,
+        owner = token_program.key(),
+        rent_exempt = enforce,
+        token::mint = mint,
+        token::authority = escrow_vault,
+    )]
+    pub escrow_vault: Account<'info, TokenAccount>,
+
+    /// Token mint.
+    pub mint: Account<'info, Mint>,
+
+    /// Token program.
+    #[account(address = token::ID)]
+    pub token_program: Program<'info, Token>,
+
+    /// Associated token program.
+    #[account(address = associated_token::ID)]
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    /// System program.
+    #[account(address = system_program::ID)]
+    pub system_program: Program<'info, System>,
+
+    /// Clock represents network time.
+    #[account(address = solana_program::sysvar::clock::ID)]
+    pub clock: Sysvar<'info, Clock>,
+
+    ///Rent for rent exempt.
+    #[account(address = solana_program::sysvar::rent::ID)]
+    pub rent: Sysvar<'info, Rent>,
+}
+
+/* clawback_merkle_pool context */
+// Accounts for clawback_merkle_pool.
+#[derive(Accounts)]
+pub struct ClawbackMerklePool<'info> {
+    /// The admin of the program.
+    #[account(
+        constraint = authority.key() == config.authority @ErrorCode::Unauthorized,
+    )]
+    pub authority: Signer<'info>,
+
+    /// The account that paid the rent at creation, it receives the rent back.
+    #[account(mut)]
+    pub payer: AccountInfo<'info>,
+
+    /// The payment vault token account.
+    #[account(
+        mut,
+        seeds = [config.to_account_info().key.as_ref()], bump = config.payment_vault_bump,
+    )]
+    pub payment_vault: Account<'info, TokenAccount>,
+
+    /// The account for saving configuration (PDA).
+    #[account(
+        seeds = [b"gyc_timelock".as_ref(), merkle_pool.mint.as_ref()],
+        bump = config.config_bump,
+        owner = id(),
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    /// merkle pool account.
+    #[account(
+        mut,
+        close = payer,
+        owner = id(),
+        constraint = merkle_pool.magic == 0x544D4C4B @ErrorCode::InvalidMagic,
+        constraint = merkle_pool.payer == payer.key() @ErrorCode::InvalidPayerMismatch,
+        constraint = merkle_pool.escrow_vault == escrow_vault.key() @ErrorCode::InvalidEscrowVaultMismatch,
+    )]
+    pub merkle_pool: Box<Account<'info, MerklePool>>,
+
+    /// escrow vault of the pool.
+    #[account(
+        mut,
+        seeds = [merkle_pool.to_account_info().key.as_ref()],
+        bump = merkle_pool.escrow_vault_bump,
+    )]
+    pub escrow_vault: Account<'info, TokenAccount>,
+
+    /// Token program.
+    #[account(address = token::ID)]
+    pub token_program: Program<'info, Token>,
+
+    /// Clock represents network time.
+    #[account(address = solana_program::sysvar::clock::ID)]
+    pub clock: Sysvar<'info, Clock>,
+}
+
+/* top_up_vesting context */
+// Accounts for top_up_vesting.
+#[derive(Accounts)]
+pub struct TopUpVesting<'info> {
+    /// The authority.
+    pub signer: Signer<'info>,
+
+    /// The payment vault token account.
+    #[account(
+        mut,
+        seeds = [config.to_account_info().key.as_ref()], bump = config.payment_vault_bump,
+        constraint = payment_vault.mint == config.mint @ErrorCode::InvalidMintMismatch,
+    )]
+    pub payment_vault: Account<'info, TokenAccount>,
+
+    /// The account for saving configuration (PDA).
+    #[account(
+        seeds = [b"gyc_timelock".as_ref(), vesting.mint.as_ref()],
+        bump = config.config_bump,
+        owner = id(),
+        constraint = config.authority == signer.key() @ErrorCode::Unauthorized,
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    /// vesting, funded by the payment vault, as cancel returns the unvested tokens to granter_token.
+    #[account(
+        mut,
+        owner = id() @ErrorCode::InvalidVestingOwner,
+        constraint = vesting.magic == 0x544D4C4B @ErrorCode::InvalidMagic,
+        constraint = !vesting.self_funded @ErrorCode::InvalidVestingKind,
+        constraint = vesting.escrow_vault == escrow_vault.key() @ErrorCode::InvalidEscrowVaultMismatch,
+        constraint = vesting.granter_token == payment_vault.key() @ErrorCode::InvalidGranterTokenMismatch,
+    )]
+    pub vesting: Box<Account<'info, Vesting>>,
+
+    /// escrow vault.
+    #[account(
+        mut,
+        seeds = [vesting.to_account_info().key.as_ref()],
+        bump = vesting.escrow_vault_bump,
+    )]
+    pub escrow_vault: Account<'info, TokenAccount>,
+
+    /// Token program.
+    #[account(address = token::ID)]
+    pub token_program: Program<'info, Token>,
+
+    /// Clock represents network time.
+    #[account(address = solana_program::sysvar::clock::ID)]
+    pub clock: Sysvar<'info, Clock>,
+}
+
+/* amend_schedule context */
+// Accounts for amend_schedule.
+#[derive(Accounts)]
+pub struct AmendSchedule<'info> {
+    /// The authority.
+    pub authority: Signer<'info>,
+
+    /// The recipient of the vesting.
+    pub recipient: Signer<'info>,
+
+    /// The account for saving configuration (PDA).
+    #[account(
+        seeds = [b"gyc_timelock".as_ref(), vesting.mint.as_ref()],
+        bump = config.config_bump,
+        owner = id(),
+        constraint = config.authority == authority.key() @ErrorCode::Unauthorized,
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    /// vesting.
+    #[account(
+        mut,
+        owner = id() @ErrorCode::InvalidVestingOwner,
+        constraint = vesting.magic == 0x544D4C4B @ErrorCode::InvalidMagic,
+        constraint = !vesting.self_funded @ErrorCode::InvalidVestingKind,
+        constraint = vesting.recipient == recipient.key() @ErrorCode::Unauthorized,
+    )]
+    pub vesting: Box<Account<'info, Vesting>>,
+
+    /// Clock represents network time.
+    #[account(address = solana_program::sysvar::clock::ID)]
+    pub clock: Sysvar<'info, Clock>,
+}
+
+/* withdraw context */
+
+// Accounts for withdraw.
+#[derive(Accounts)]
+pub struct Withdraw<'info> {
+    /// signer allowed to withdraw by the vesting (anyone, recipient or delegate).
+    pub signer: Signer<'info>,
+
+    /// the recipient of token account, any token account of the mint when the recipient signs.
+    #[account(
+        mut,
+        constraint = recipient_token.mint == mint.key() @ErrorCode::InvalidMintMismatch,
+    )]
+    pub recipient_token: Account<'info, TokenAccount>,
+
+    /// vesting account.
+    #[account(
+        mut,
+        owner = id() @ErrorCode::InvalidVestingOwner,
+        constraint = vesting.magic == 0x544D4C4B @ErrorCode::InvalidMagic,
+        constraint = !vesting.self_funded @ErrorCode::InvalidVestingKind,
+        constraint = vesting.escrow_vault == escrow_vault.key() @ErrorCode::InvalidEscrowVaultMismatch,
+        constraint = vesting.withdraw_authority.allows(&signer.key(), &vesting.recipient, &vesting.withdraw_delegate) @ErrorCode::Unauthorized,
+        constraint = vesting.recipient_token == recipient_token.key() || vesting.recipient == signer.key() @ErrorCode::InvalidRecipientTokenMismatch,
+    )]
+    pub vesting: Box<Account<'info, Vesting>>,
+
+    /// The account for saving configuration (PDA).
+    #[account(
+        seeds = [b"gyc_timelock".as_ref(), vesting.mint.as_ref()],
+        bump = config.config_bump,
+        owner = id(),
+        constraint = !config.paused @ErrorCode::Paused,
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    /// escrow vault.
+    #[account(
+        mut,
+        constraint = escrow_vault.mint == mint.key() @ErrorCode::InvalidMintMismatch,
+        seeds = [vesting.to_account_info().key.as_ref()],
+        bump = vesting.escrow_vault_bump,
+    )]
+    pub escrow_vault: Account<'info, TokenAccount>,
+
+    /// Token mint.
+    #[account(address = vesting.mint @ErrorCode::InvalidMintMismatch,)]
+    pub mint: Account<'info, Mint>,
+
+    /// Token program.
+    #[account(address = token::ID)]
+    pub token_program: Program<'info, Token>,
+
+    /// Clock represents network time.
+    #[account(address = solana_program::sysvar::clock::ID)]
+    pub clock: Sysvar<'info, Clock>,
+}
+
+/* cancel context */
+// Accounts for cancel.
+#[derive(Accounts)]
+pub struct CancelVesting<'info> {
+    /// signer allowed to cancel by the vesting (canceller or recipient).
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    /// The account that paid the rent at creation, it receives the rent back.
+    #[account(mut)]
+    pub payer: AccountInfo<'info>,
+
+    /// The token account that funded the vesting, the unvested tokens go back to it.
+    #[account(
+        mut,
+        constraint = granter_token.mint == mint.key() @ErrorCode::InvalidMintMismatch,
+    )]
+    pub granter_token: Account<'info, TokenAccount>,
+
+    /// The account for saving configuration (PDA).
+    #[account(
+        seeds = [b"gyc_timelock".as_ref(), vesting.mint.as_ref()],
+        bump = config.config_bump,
+        owner = id(),
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    /// vesting.
+    #[account(
+        mut,
+        close = payer,
+        owner = id() @ErrorCode::InvalidVestingOwner,
+        constraint = vesting.magic == 0x544D4C4B @ErrorCode::InvalidMagic,
+        constraint = !vesting.self_funded @ErrorCode::InvalidVestingKind,
+        constraint = vesting.cancelable_by.allows(&signer.key(), &config.canceller, &vesting.recipient) @ErrorCode::Unauthorized,
+        constraint = vesting.payer == payer.key() @ErrorCode::InvalidPayerMismatch,
+        constraint = vesting.escrow_vault == escrow_vault.key() @ErrorCode::InvalidEscrowVaultMismatch,
+        constraint = vesting.granter_token == granter_token.key() @ErrorCode::InvalidGranterTokenMismatch,
+    )]
+    pub vesting: Box<Account<'info, Vesting>>,
+
+    /// the recipient of token account.
+    #[account(
+        mut,
+        constraint = recipient_token.mint == mint.key() @ErrorCode::InvalidMintMismatch,
+        constraint = vesting.recipient_token == recipient_token.key() @ErrorCode::InvalidRecipientTokenMismatch,
+    )]
+    pub recipient_token: Account<'info, TokenAccount>,
+
+    /// escrow vault.
+    #[account(
+        mut,
+        constraint = escrow_vault.mint == mint.key()  @ErrorCode::InvalidMintMismatch,
+        seeds = [vesting.to_account_info().key.as_ref()],
+        bump = vesting.escrow_vault_bump,
+    )]
+    pub escrow_vault: Account<'info, TokenAccount>,
+
+    /// Token mint.
+    #[account(address = vesting.mint @ErrorCode::InvalidMintMismatch,)]
+    pub mint: Account<'info, Mint>,
+
+    /// Token program.
+    #[account(address = token::ID)]
+    pub token_program: Program<'info, Token>,
+
+    /// Clock represents network time.
+    #[account(address = solana_program::sysvar::clock::ID)]
+    pub clock: Sysvar<'info, Clock>,
+}
+
+/* withdraw_self_funded context */
+// Accounts for withdraw_self_funded.
+#[derive(Accounts)]
+pub struct WithdrawSelfFunded<'info> {
+    /// signer allowed to withdraw by the vesting (anyone, recipient or delegate).
+    pub signer: Signer<'info>,
+
+    /// the recipient of token account, any token account of the mint when the recipient signs.
+    #[account(
+        mut,
+        constraint = recipient_token.mint == mint.key() @ErrorCode::InvalidMintMismatch,
+    )]
+    pub recipient_token: Account<'info, TokenAccount>,
+
+    /// vesting account.
+    #[account(
+        mut,
+        owner = id() @ErrorCode::InvalidVestingOwner,
+        constraint = vesting.magic == 0x544D4C4B @ErrorCode::InvalidMagic,
+        constraint = vesting.self_funded @ErrorCode::InvalidVestingKind,
+        constraint = vesting.escrow_vault == escrow_vault.key() @ErrorCode::InvalidEscrowVaultMismatch,
+        constraint = vesting.withdraw_authority.allows(&signer.key(), &vesting.recipient, &vesting.withdraw_delegate) @ErrorCode::Unauthorized,
+        constraint = vesting.recipient_token == recipient_token.key() || vesting.recipient == signer.key() @ErrorCode::InvalidRecipientTokenMismatch,
+    )]
+    pub vesting: Box<Account<'info, Vesting>>,
+
+    /// escrow vault.
+    #[account(
+        mut,
+        constraint = escrow_vault.mint == mint.key() @ErrorCode::InvalidMintMismatch,
+        seeds = [vesting.to_account_info().key.as_ref()],
+        bump = vesting.escrow_vault_bump,
+    )]
+    pub escrow_vault: Account<'info, TokenAccount>,
+
+    /// Token mint.
+    #[account(address = vesting.mint @ErrorCode::InvalidMintMismatch,)]
+    pub mint: Account<'info, Mint>,
+
+    /// Token program.
+    #[account(address = token::ID)]
+    pub token_program: Program<'info, Token>,
+
+    /// Clock represents network time.
+    #[account(address = solana_program::sysvar::clock::ID)]
+    pub clock: Sysvar<'info, Clock>,
+}
+
+/* cancel_self_funded context */
+// Accounts for cancel_self_funded.
+#[derive(Accounts)]
+pub struct CancelSelfFunded<'info> {
+    /// signer allowed to cancel by the vesting (granter or recipient).
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    /// The account that paid the rent at creation, it receives the rent back.
+    #[account(mut)]
+    pub payer: AccountInfo<'info>,
+
+    /// The token account that funded the vesting, the unvested tokens go back to it.
+    #[account(
+        mut,
+        constraint = granter_token.mint == mint.key() @ErrorCode::InvalidMintMismatch,
+    )]
+    pub granter_token: Account<'info, TokenAccount>,
+
+    /// vesting.
+    #[account(
+        mut,
+        close = payer,
+        owner = id() @ErrorCode::InvalidVestingOwner,
+        constraint = vesting.magic == 0x544D4C4B @ErrorCode::InvalidMagic,
+        constraint = vesting.self_funded @ErrorCode::InvalidVestingKind,
+        constraint = vesting.cancelable_by.allows(&signer.key(), &vesting.granter, &vesting.recipient) @ErrorCode::Unauthorized,
+        constraint = vesting.payer == payer.key() @ErrorCode::InvalidPayerMismatch,
+        constraint = vesting.escrow_vault == escrow_vault.key() @ErrorCode::InvalidEscrowVaultMismatch,
+        constraint = vesting.granter_token == granter_token.key() @ErrorCode::InvalidGranterTokenMismatch,
+    )]
+    pub vesting: Box<Account<'info, Vesting>>,
+
+    /// the recipient of token account.
+    #[account(
+        mut,
+        constraint = recipient_token.mint == mint.key() @ErrorCode::InvalidMintMismatch,
+        constraint = vesting.recipient_token == recipient_token.key() @ErrorCode::InvalidRecipientTokenMismatch,
+    )]
+    pub recipient_token: Account<'info, TokenAccount>,
+
+    /// escrow vault.
+    #[account(
+        mut,
+        constraint = escrow_vault.mint == mint.key()  @ErrorCode::InvalidMintMismatch,
+        seeds = [vesting.to_account_info().key.as_ref()],
+        bump = vesting.escrow_vault_bump,
+    )]
+    pub escrow_vault: Account<'info, TokenAccount>,
+
+    /// Token mint.
+    #[account(address = vesting.mint @ErrorCode::InvalidMintMismatch,)]
+    pub mint: Account<'info, Mint>,
+
+    /// Token program.
+    #[account(address = token::ID)]
+    pub token_program: Program<'info, Token>,
+
+    /// Clock represents network time.
+    #[account(address = solana_program::sysvar::clock::ID)]
+    pub clock: Sysvar<'info, Clock>,
+}
+
+/* close_vesting context */
+// Accounts for close_vesting.
+#[derive(Accounts)]
+pub struct CloseVesting<'info> {
+    /// The account that paid the rent at creation, it receives the rent back.
+    #[account(mut)]
+    pub payer: AccountInfo<'info>,
+
+    /// vesting.
+    #[account(
+        mut,
+        close = payer,
+        owner = id() @ErrorCode::InvalidVestingOwner,
+        constraint = vesting.magic == 0x544D4C4B @ErrorCode::InvalidMagic,
+        constraint = vesting.remaining_amount == 0 @ErrorCode::VestingNotEmpty,
+        constraint = vesting.payer == payer.key() @ErrorCode::InvalidPayerMismatch,
+        constraint = vesting.escrow_vault == escrow_vault.key() @ErrorCode::InvalidEscrowVaultMismatch,
+    )]
+    pub vesting: Box<Account<'info, Vesting>>,
+
+    /// escrow vault.
+    #[account(
+        mut,
+        seeds = [vesting.to_account_info().key.as_ref()],
+        bump = vesting.escrow_vault_bump,
+    )]
+    pub escrow_vault: Account<'info, TokenAccount>,
+
+    /// Token program.
+    #[account(address = token::ID)]
+    pub token_program: Program<'info, Token>,
+}
+
+/* transfer_vesting context */
+// Accounts for transfer_vesting.
+#[derive(Accounts)]
+pub struct TransferVesting<'info> {
+    /// The current recipient, it pays for the new associated token account if needed.
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    /// The authority, it must sign when the config requires transfer approval.
+    #[account(
+        constraint = !config.transfer_requires_approval
+            || (authority.is_signer && authority.key() == config.authority) @ErrorCode::Unauthorized,
+    )]
+    pub authority: AccountInfo<'info>,
+
+    /// The account for saving configuration (PDA).
+    #[account(
+        seeds = [b"gyc_timelock".as_ref(), vesting.mint.as_ref()],
+        bump = config.config_bump,
+        owner = id(),
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    /// vesting.
+    #[account(
+        mut,
+        owner = id() @ErrorCode::InvalidVestingOwner,
+        constraint = vesting.magic == 0x544D4C4B @ErrorCode::InvalidMagic,
+        constraint = !vesting.self_funded @ErrorCode::InvalidVestingKind,
+        constraint = vesting.recipient == signer.key() @ErrorCode::Unauthorized,
+    )]
+    pub vesting: Box<Account<'info, Vesting>>,
+
+    /// the new recipient of main account
+    pub new_recipient: AccountInfo<'info>,
+    /// the new recipient of token account
+    #[account(mut)]
+    pub new_recipient_token: AccountInfo<'info>,
+
+    /// Token mint.
+    #[account(address = vesting.mint @ErrorCode::InvalidMintMismatch,)]
+    pub mint: Account<'info, Mint>,
+
+    /// Token program.
+    #[account(address = token::ID)]
+    pub token_program: Program<'info, Token>,
+
+    /// Associated token program.
+    #[account(address = associated_token::ID)]
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    /// System program.
+    #[account(address = system_program::ID)]
+    pub system_program: Program<'info, System>,
+
+    ///Rent for rent exempt.
+    #[account(address = solana_program::sysvar::rent::ID)]
+    pub rent: Sysvar<'info, Rent>,
+}
+
+/* transfer_vesting_self_funded context */
+// Accounts for transfer_vesting_self_funded, the config is not involved.
+#[derive(Accounts)]
+pub struct TransferVestingSelfFunded<'info> {
+    /// The current recipient, it pays for the new associated token account if needed.
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    /// vesting.
+    #[account(
+        mut,
+        owner = id() @ErrorCode::InvalidVestingOwner,
+        constraint = vesting.magic == 0x544D4C4B @ErrorCode::InvalidMagic,
+        constraint = vesting.self_funded @ErrorCode::InvalidVestingKind,
+        constraint = vesting.recipient == signer.key() @ErrorCode::Unauthorized,
+    )]
+    pub vesting: Box<Account<'info, Vesting>>,
+
+    /// the new recipient of main account
+    pub new_recipient: AccountInfo<'info>,
+    /// the new recipient of token account
+    #[account(mut)]
+    pub new_recipient_token: AccountInfo<'info>,
+
+    /// Token mint.
+    #[account(address = vesting.mint @ErrorCode::InvalidMintMismatch,)]
+    pub mint: Account<'info, Mint>,
+
+    /// Token program.
+    #[account(address = token::ID)]
+    pub token_program: Program<'info, Token>,
+
+    /// Associated token program.
+    #[account(address = associated_token::ID)]
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    /// System program.
+    #[account(address = system_program::ID)]
+    pub system_program: Program<'info, System>,
+
+    ///Rent for rent exempt.
+    #[account(address = solana_program::sysvar::rent::ID)]
+    pub rent: Sysvar<'info, Rent>,
+}
+
+/* release_milestone context */
+// Accounts for release_milestone.
+#[derive(Accounts)]
+pub struct ReleaseMilestone<'info> {
+    /// The authority or the milestone oracle of the vesting.
+    pub signer: Signer<'info>,
+
+    /// The account for saving configuration (PDA).
+    #[account(
+        seeds = [b"gyc_timelock".as_ref(), vesting.mint.as_ref()],
+        bump = config.config_bump,
+        owner = id(),
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    /// vesting.
+    #[account(
+        mut,
+        owner = id() @ErrorCode::InvalidVestingOwner,
+        constraint = vesting.magic == 0x544D4C4B @ErrorCode::InvalidMagic,
+        constraint = !vesting.self_funded @ErrorCode::InvalidVestingKind,
+        constraint = config.authority == signer.key()
+            || (vesting.milestone_oracle != Pubkey::default() && vesting.milestone_oracle == signer.key()) @ErrorCode::Unauthorized,
+    )]
+    pub vesting: Box<Account<'info, Vesting>>,
+
+    /// Clock represents network time.
+    #[account(address = solana_program::sysvar::clock::ID)]
+    pub clock: Sysvar<'info, Clock>,
+}
+
+/* fund_payment_vault context */
+// Accounts for fund_payment_vault.
+#[derive(Accounts)]
+pub struct FundPaymentVault<'info> {
+    /// The account that must have permission to invoke this instruction, owner of the source token account.
+    pub signer: Signer<'info>,
+
+    /// The source token account.
+    #[account(
+        mut,
+        constraint = source_token.mint == config.mint @ErrorCode::InvalidMintMismatch,
+    )]
+    pub source_token: Account<'info, TokenAccount>,
+
+    /// The payment vault token account.
+    #[account(
+        mut,
+        seeds = [config.to_account_info().key.as_ref()], bump = config.payment_vault_bump,
+        constraint = payment_vault.mint == config.mint @ErrorCode::InvalidMintMismatch,
+    )]
+    pub payment_vault: Account<'info, TokenAccount>,
+
+    /// The account for saving configuration (PDA).
+    #[account(
+        seeds = [b"gyc_timelock".as_ref(), mint.key().as_ref()],
+        bump = config.config_bump,
+        owner = id(),
+        constraint = config.authority == signer.key() @ErrorCode::Unauthorized,
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    /// Token mint.
+    #[account(address = config.mint @ErrorCode::InvalidMintMismatch,)]
+    pub mint: Account<'info, Mint>,
+
+    /// Token program.
+    #[account(address = token::ID)]
+    pub token_program: Program<'info, Token>,
+}
+
+/* withdraw_payment_vault context */
+// Accounts for withdraw_payment_vault.
+#[derive(Accounts)]
+pub struct WithdrawPaymentVault<'info> {
+    /// The account that must have permission to invoke this instruction.
+    pub signer: Signer<'info>,
+
+    /// The destination token account.
+    #[account(
+        mut,
+        constraint = destination_token.mint == config.mint @ErrorCode::InvalidMintMismatch,
+    )]
+    pub destination_token: Account<'info, TokenAccount>,
+
+    /// The payment vault token account.
+    #[account(
+        mut,
+        seeds = [config.to_account_info().key.as_ref()], bump = config.payment_vault_bump,
+        constraint = payment_vault.mint == config.mint @ErrorCode::InvalidMintMismatch,
+    )]
+    pub payment_vault: Account<'info, TokenAccount>,
+
+    /// The account for saving configuration (PDA).
+    #[account(
+        seeds = [b"gyc_timelock".as_ref(), mint.key().as_ref()],
+        bump = config.config_bump,
+        owner = id(),
+        constraint = config.authority == signer.key() @ErrorCode::Unauthorized,
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    /// Token mint.
+    #[account(address = config.mint @ErrorCode::InvalidMintMismatch,)]
+    pub mint: Account<'info, Mint>,
+
+    /// Token program.
+    #[account(address = token::ID)]
+    pub token_program: Program<'info, Token>,
+}
+
+
+/* propose_authority context */
+// Accounts for propose_authority.
+#[derive(Accounts)]
+pub struct ProposeAuthority<'info> {
+    /// The current authority.
+    pub signer: Signer<'info>,
+
+    /// The account for saving configuration (PDA).
+    #[account(
+        mut,
+        seeds = [b"gyc_timelock".as_ref(), config.mint.as_ref()],
+        bump = config.config_bump,
         owner = id(),
-        rent_exempt = enforce,
+        constraint = config.authority == signer.key() @ErrorCode::Unauthorized,
+    )]
+    pub config: Box<Account<'info, Config>>,
+}
+
+/* accept_authority context */
+// Accounts for accept_authority.
+#[derive(Accounts)]
+pub struct AcceptAuthority<'info> {
+    /// The pending authority.
+    pub signer: Signer<'info>,
+
+    /// The account for saving configuration (PDA).
+    #[account(
+        mut,
+        seeds = [b"gyc_timelock".as_ref(), config.mint.as_ref()],
+        bump = config.config_bump,
+        owner = id(),
+        constraint = config.pending_authority != Pubkey::default() @ErrorCode::Unauthorized,
+        constraint = config.pending_authority == signer.key() @ErrorCode::Unauthorized,
+    )]
+    pub config: Box<Account<'info, Config>>,
+}
+
+
+/* set_roles context */
+// Accounts for set_roles.
+#[derive(Accounts)]
+pub struct SetRoles<'info> {
+    /// The admin.
+    pub signer: Signer<'info>,
+
+    /// The account for saving configuration (PDA).
+    #[account(
+        mut,
+        seeds = [b"gyc_timelock".as_ref(), config.mint.as_ref()],
+        bump = config.config_bump,
+        owner = id(),
+        constraint = config.authority == signer.key() @ErrorCode::Unauthorized,
+    )]
+    pub config: Box<Account<'info, Config>>,
+}
+
+
+/* set_transfer_approval context */
+// Accounts for set_transfer_approval.
+#[derive(Accounts)]
+pub struct SetTransferApproval<'info> {
+    /// The admin.
+    pub signer: Signer<'info>,
+
+    /// The account for saving configuration (PDA).
+    #[account(
+        mut,
+        seeds = [b"gyc_timelock".as_ref(), config.mint.as_ref()],
+        bump = config.config_bump,
+        owner = id(),
+        constraint = config.authority == signer.key() @ErrorCode::Unauthorized,
+    )]
+    pub config: Box<Account<'info, Config>>,
+}
+
+/* pause and unpause context */
+// Accounts for pause and unpause.
+#[derive(Accounts)]
+pub struct SetPaused<'info> {
+    /// The pauser.
+    pub signer: Signer<'info>,
+
+    /// The account for saving configuration (PDA).
+    #[account(
+        mut,
+        seeds = [b"gyc_timelock".as_ref(), config.mint.as_ref()],
+        bump = config.config_bump,
+        owner = id(),
+        constraint = config.pauser == signer.key() @ErrorCode::Unauthorized,
     )]
     pub config: Box<Account<'info, Config>>,
+}
+
+// --------------------------------
+// PDA Structs
+// --------------------------------
+
+// A struct controls vesting.
+#[account]
+pub struct Vesting {
+    /// Magic bytes, always fill the string "TMLK"(timelock).
+    pub magic: u32,
+    /// Contract version, release rates are in percent before version 2 and in basis points since.
+    pub version: u32,
+    /// The escrow vault bump.
+    pub escrow_vault_bump: u8,
+    /// Vesting bump.
+    pub vesting_bump: u8,
+    /// The vesting id.
+    pub vesting_id: u64,
+    /// The vesting name.
+    pub vesting_name: [u8; 32],
+    /// The investor wallet address.
+    pub investor_wallet_address: [u8; 64],
+
+    /// Amount of funds withdrawn.
+    pub withdrawn_amount: u64,
+    /// Remaining amount of the tokens in the escrow account.
+    pub remaining_amount: u64,
+    /// The starting balance of this vesting account, i.e., how much was
+    /// originally deposited.
+    pub total_amount: u64,
+
+    /// Pubkey of the granter main account (signer).
+    pub granter: Pubkey,
+    /// Pubkey of the granter token account.
+    pub granter_token: Pubkey,
+    /// Pubkey of the recipient main account, it changes on transfer_vesting.
+    pub recipient: Pubkey,
+    /// Pubkey of the recipient token account.
+    pub recipient_token: Pubkey,
+    /// Pubkey of the token mint.
+    pub mint: Pubkey,
+    /// Pubkey of the escrow vault account holding the locked tokens.
+    pub escrow_vault: Pubkey,
+
+    /// Timestamp when stream was created.
+    pub created_ts: u64,
+    /// Timestamp when the tokens start vesting.
+    pub start_ts: u64,
+    /// Timestamp when all tokens are fully vested.
+    pub end_ts: u64,
+
+    /// Time step (period) per which the vesting occurs, in units of period_unit.
+    pub period: u64,
+    /// The unit of period.
+    pub period_unit: PeriodUnit,
+    /// Vesting contract "cliff" timestamp.
+    pub cliff: u64,
+    /// The rate of amount unlocked at the "cliff" timestamp, over rate_denominator().
+    pub cliff_release_rate: u64,
+    /// Amount unlocked at the "cliff" timestamp.
+    pub cliff_amount: u64,
+    /// The rate of amount unlocked at TGE, over rate_denominator().
+    pub tge_release_rate: u64,
+    /// Amount unlocked at TGE.
+    pub tge_amount: u64,
+
+    /// Who is allowed to cancel the vesting.
+    pub cancelable_by: CancelAuthority,
+
+    /// Pubkey of the account that paid the rent of the vesting and escrow vault.
+    pub payer: Pubkey,
+
+    /// Who is allowed to withdraw the unlocked tokens.
+    pub withdraw_authority: WithdrawAuthority,
+    /// Pubkey of the delegate allowed to withdraw on behalf of the recipient.
+    pub withdraw_delegate: Pubkey,
+
+    /// Pubkey of the recipient at creation, the vesting PDA is keyed by it.
+    pub original_recipient: Pubkey,
+
+    /// How the tokens unlock.
+    pub kind: VestingKind,
+    /// Pubkey of the account allowed to release milestones besides the authority.
+    pub milestone_oracle: Pubkey,
+    /// The milestone tranches, used by VestingKind::Milestone, MAX_MILESTONES of them. The length
+    /// is spelled out as the IDL parser only reads literal array lengths.
+    pub milestones: [Milestone; 8],
+
+    /// How the unlocked amount moves between breakpoints, used by VestingKind::Piecewise.
+    pub interpolation: Interpolation,
+    /// Amount topped up and released immediately, outside the schedule.
+    pub immediate_amount: u64,
+    /// Linear periods elapsed at the last pro-rata top-up, the linear unlocking restarts from there.
+    pub rebase_periods: u64,
+    /// Amount unlocked linearly at the last pro-rata top-up.
+    pub rebase_unlocked: u64,
+
+    /// Whether the signer funded the vesting without the config, see create_self_funded_vesting.
+    pub self_funded: bool,
+
+    /// The breakpoints of the schedule, used by VestingKind::Piecewise. Must stay the last field,
+    /// the vesting account is sized by their number.
+    pub breakpoints: Vec<Breakpoint>,
+}
+
+/// The maximum number of breakpoints of a piecewise schedule.
+pub const MAX_BREAKPOINTS: usize = 64;
+
+/// The serialized size of a breakpoint.
+pub const BREAKPOINT_SIZE: usize = 16;
+
+/// The version of vestings created by this program. Version 3 changed the layout, it adds self_funded.
+pub const VESTING_VERSION: u32 = 3;
+
+/// The first version with release rates in basis points.
+pub const BPS_RATES_VERSION: u32 = 2;
+
+/// The denominator of release rates in percent.
+pub const PERCENT_DENOMINATOR: u64 = 100;
+
+impl Vesting {
+    /// The denominator of the tge and cliff release rates, percent for vestings created before version 2.
+    pub fn rate_denominator(&self) -> u64 {
+        if self.version >= BPS_RATES_VERSION {
+            BPS_DENOMINATOR
+        } else {
+            PERCENT_DENOMINATOR
+        }
+    }
+}
+
+/// The maximum number of milestone tranches of a vesting.
+pub const MAX_MILESTONES: usize = 8;
+
+// Fails to compile when the length of Vesting::milestones drifts from MAX_MILESTONES.
+const _: fn(&Vesting) -> [Milestone; MAX_MILESTONES] = |vesting| vesting.milestones;
+
+/// The denominator of rates in basis points.
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
+// How the tokens of a vesting unlock, chosen at creation.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq)]
+pub enum VestingKind {
+    /// Tge, cliff and linear unlocking by the timestamps.
+    Schedule,
+    /// Tranches released by the authority or the milestone oracle.
+    Milestone,
+    /// Cumulative rates at breakpoint timestamps.
+    Piecewise,
+}
+
+/// Seconds per day.
+pub const SECONDS_PER_DAY: u64 = 86_400;
+
+/// The last civil year that fits in u64 timestamps.
+pub const MAX_CIVIL_YEAR: i128 = 584_554_051_223;
+
+// The unit of the vesting period.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq)]
+pub enum PeriodUnit {
+    /// Fixed number of seconds.
+    Seconds,
+    /// Calendar months, unlocking on the same day of month as the linear start.
+    Month,
+    /// Calendar quarters, i.e. three calendar months.
+    Quarter,
+    /// Calendar years, i.e. twelve calendar months.
+    Year,
+}
+
+impl PeriodUnit {
+    /// The number of calendar months of the unit, none for seconds.
+    pub fn months(&self) -> Option<u64> {
+        match self {
+            PeriodUnit::Seconds => None,
+            PeriodUnit::Month => Some(1),
+            PeriodUnit::Quarter => Some(3),
+            PeriodUnit::Year => Some(12),
+        }
+    }
+}
+
+// The amendable terms of a linear schedule.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq)]
+pub struct ScheduleTerms {
+    /// Timestamp when all tokens are fully vested.
+    pub end_ts: u64,
+    /// Time step (period) per which the vesting occurs, in units of the vesting period unit.
+    pub period: u64,
+    /// Vesting contract "cliff" timestamp.
+    pub cliff: u64,
+    /// The rate of amount unlocked at the "cliff" timestamp.
+    pub cliff_release_rate: u64,
+    /// The rate of amount unlocked at TGE.
+    pub tge_release_rate: u64,
+}
+
+impl ScheduleTerms {
+    /// The current terms of `vesting`.
+    pub fn of(vesting: &Vesting) -> ScheduleTerms {
+        ScheduleTerms {
+            end_ts: vesting.end_ts,
+            period: vesting.period,
+            cliff: vesting.cliff,
+            cliff_release_rate: vesting.cliff_release_rate,
+            tge_release_rate: vesting.tge_release_rate,
+        }
+    }
+}
+
+/// The maximum number of entries of create_vestings_batch. Each entry adds four accounts and its
+/// terms to the instruction, so a fourth entry of a distinct recipient no longer fits in the 1232
+/// bytes of a transaction, see batch_of_max_entries_fits_in_a_transaction.
+pub const MAX_BATCH_ENTRIES: usize = 3;
+
+/// The number of remaining accounts of each batch entry: recipient, recipient token, vesting and escrow vault.
+pub const BATCH_ACCOUNTS_PER_ENTRY: usize = 4;
+
+// A vesting of create_vestings_batch.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq)]
+pub struct BatchEntry {
+    /// Pubkey of the recipient main account.
+    pub recipient: Pubkey,
+    /// The starting balance of the vesting.
+    pub total_amount: u64,
+    /// The vesting id.
+    pub vesting_id: u64,
+    /// The vesting bump.
+    pub vesting_bump: u8,
+    /// The escrow vault bump.
+    pub escrow_vault_bump: u8,
+    /// Timestamp when the tokens start vesting.
+    pub start_ts: u64,
+    /// The unit of period.
+    pub period_unit: PeriodUnit,
+    /// The end, period, cliff and release rates of the schedule, in basis points.
+    pub terms: ScheduleTerms,
+}
+
+/// The hash prefix of merkle pool leaves.
+pub const MERKLE_LEAF_PREFIX: u8 = 0;
+
+/// The hash prefix of merkle pool inner nodes.
+pub const MERKLE_NODE_PREFIX: u8 = 1;
+
+// A leaf of a merkle pool, the vesting its recipient claims.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq)]
+pub struct MerkleLeaf {
+    /// The index of the leaf, the claimed vesting is keyed by it.
+    pub index: u64,
+    /// Pubkey of the recipient main account.
+    pub recipient: Pubkey,
+    /// The starting balance of the vesting.
+    pub total_amount: u64,
+    /// Timestamp when the tokens start vesting.
+    pub start_ts: u64,
+    /// The unit of period.
+    pub period_unit: PeriodUnit,
+    /// The end, period, cliff and release rates of the schedule, in basis points.
+    pub terms: ScheduleTerms,
+}
+
+// How the unlocked amount of a piecewise schedule moves between breakpoints.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq)]
+pub enum Interpolation {
+    /// The rate of a breakpoint holds until the next one.
+    Step,
+    /// The rate grows linearly from a breakpoint to the next one.
+    Linear,
+}
+
+// A breakpoint of a piecewise schedule.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct Breakpoint {
+    /// Timestamp of the breakpoint.
+    pub ts: u64,
+    /// The cumulative unlocked rate at the timestamp in basis points.
+    pub rate: u64,
+}
+
+// A milestone tranche.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct Milestone {
+    /// The rate of the total amount in basis points, zero marks an unused slot.
+    pub rate: u64,
+    /// Amount unlocked by the tranche.
+    pub amount: u64,
+    /// Timestamp when the tranche was released, zero while locked.
+    pub released_ts: u64,
+}
+
+// Who is allowed to cancel a vesting, chosen at creation and never changed.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq)]
+pub enum CancelAuthority {
+    /// Irrevocable, nobody can cancel the vesting.
+    None,
+    /// Only the config canceller, the granter of a self-funded vesting, can cancel the vesting.
+    Authority,
+    /// Only the recipient can cancel the vesting.
+    Recipient,
+    /// Both the config canceller (or the granter) and the recipient can cancel the vesting.
+    Both,
+}
+
+impl CancelAuthority {
+    /// Whether `signer` is allowed to cancel.
+    pub fn allows(&self, signer: &Pubkey, canceller: &Pubkey, recipient: &Pubkey) -> bool {
+        match self {
+            CancelAuthority::None => false,
+            CancelAuthority::Authority => signer == canceller,
+            CancelAuthority::Recipient => signer == recipient,
+            CancelAuthority::Both => signer == canceller || signer == recipient,
+        }
+    }
+}
+
+// Who is allowed to withdraw the unlocked tokens of a vesting, chosen at creation.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq)]
+pub enum WithdrawAuthority {
+    /// Permissionless, anyone can push the unlocked tokens to the recipient token account.
+    Anyone,
+    /// Only the recipient can withdraw.
+    Recipient,
+    /// Either the recipient or the withdraw delegate can withdraw.
+    RecipientOrDelegate,
+}
+
+impl WithdrawAuthority {
+    /// Whether `signer` is allowed to withdraw.
+    pub fn allows(&self, signer: &Pubkey, recipient: &Pubkey, delegate: &Pubkey) -> bool {
+        match self {
+            WithdrawAuthority::Anyone => true,
+            WithdrawAuthority::Recipient => signer == recipient,
+            WithdrawAuthority::RecipientOrDelegate => signer == recipient || signer == delegate,
+        }
+    }
+}
+
+// A struct controls Config, keyed by the token mint.
+#[account]
+pub struct Config {
+    /// The PDA bump of config account.
+    pub config_bump: u8,
+
+    /// The PDA bump of payment vault token account.
+    pub payment_vault_bump: u8,
+
+    /// The payment vault token account (PDA).
+    pub payment_vault: Pubkey,
+
+    /// The admin, it manages the payment vault and the roles below.
+    pub authority: Pubkey,
+
+    /// token mint.
+    pub mint: Pubkey,
+
+    /// The proposed authority, it becomes the authority once it accepts.
+    pub pending_authority: Pubkey,
+
+    /// The account that have permission to invoke create_vesting instruction.
+    pub creator: Pubkey,
+
+    /// The account that have permission to cancel vestings cancelable by the authority.
+    pub canceller: Pubkey,
+
+    /// The account that have permission to pause and unpause the program.
+    pub pauser: Pubkey,
+
+    /// Whether create_vesting and withdraw are paused, cancel still works while paused.
+    pub paused: bool,
+
+    /// Whether transfer_vesting must be co-signed by the authority.
+    pub transfer_requires_approval: bool,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        unsafe { std::mem::zeroed() }
+    }
+}
+
+// The config before it was keyed by mint, at the seeds [b"gyc_timelock"]. It is only read by
+// migrate_config, so it keeps the discriminator of Config.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ConfigV1 {
+    /// The PDA bump of config account.
+    pub config_bump: u8,
+
+    /// The PDA bump of payment vault token account.
+    pub payment_vault_bump: u8,
+
+    /// The payment vault token account (PDA).
+    pub payment_vault: Pubkey,
+
+    /// The account that have permission to invoke create_vesting and cancel instruction instruction.
+    pub authority: Pubkey,
+
+    /// token mint.
+    pub mint: Pubkey,
+}
+
+impl AccountSerialize for ConfigV1 {}
+
+impl AccountDeserialize for ConfigV1 {
+    fn try_deserialize(buf: &mut &[u8]) -> std::result::Result<Self, ProgramError> {
+        if buf.len() < 8 || buf[..8] != Config::discriminator() {
+            return Err(anchor_lang::__private::ErrorCode::AccountDiscriminatorMismatch.into());
+        }
+        Self::try_deserialize_unchecked(buf)
+    }
+
+    fn try_deserialize_unchecked(buf: &mut &[u8]) -> std::result::Result<Self, ProgramError> {
+        let mut data: &[u8] = &buf[8..];
+        AnchorDeserialize::deserialize(&mut data)
+            .map_err(|_| anchor_lang::__private::ErrorCode::AccountDidNotDeserialize.into())
+    }
+}
+
+impl Owner for ConfigV1 {
+    fn owner() -> Pubkey {
+        crate::ID
+    }
+}
+
+// The vesting of the first version, before the fields of the later versions were added. It is
+// only read by migrate_vesting, so it keeps the discriminator of Vesting.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct VestingV1 {
+    pub magic: u32,
+    pub version: u32,
+    pub escrow_vault_bump: u8,
+    pub vesting_bump: u8,
+    pub vesting_id: u64,
+    pub vesting_name: [u8; 32],
+    pub investor_wallet_address: [u8; 64],
+
+    pub withdrawn_amount: u64,
+    pub remaining_amount: u64,
+    pub total_amount: u64,
+
+    pub granter: Pubkey,
+    pub granter_token: Pubkey,
+    pub recipient: Pubkey,
+    pub recipient_token: Pubkey,
+    pub mint: Pubkey,
+    pub escrow_vault: Pubkey,
+
+    pub created_ts: u64,
+    pub start_ts: u64,
+    pub end_ts: u64,
+    pub accounting_ts: u64,
+    pub last_withdrawn_at: u64,
+
+    pub period: u64,
+    pub cliff: u64,
+    pub cliff_release_rate: u64,
+    pub cliff_amount: u64,
+    pub tge_release_rate: u64,
+    pub tge_amount: u64,
+    pub periodic_unlock_amount: u64,
+}
+
+/// The serialized size of a vesting of the first version.
+pub const VESTING_V1_SIZE: usize = 426;
+
+impl VestingV1 {
+    /// Returns the vesting in the current layout. It keeps its version, so the release rates stay
+    /// in percent, and the permissions of the first version: anyone withdraws to the recipient
+    /// token account and the config canceller cancels back to `payment_vault`.
+    pub fn migrate(&self, payment_vault: Pubkey, payer: Pubkey) -> Vesting {
+        Vesting {
+            magic: self.magic,
+            version: self.version,
+            escrow_vault_bump: self.escrow_vault_bump,
+            vesting_bump: self.vesting_bump,
+            vesting_id: self.vesting_id,
+            vesting_name: self.vesting_name,
+            investor_wallet_address: self.investor_wallet_address,
+
+            withdrawn_amount: self.withdrawn_amount,
+            remaining_amount: self.remaining_amount,
+            total_amount: self.total_amount,
+
+            granter: payment_vault,
+            granter_token: payment_vault,
+            recipient: self.recipient,
+            original_recipient: self.recipient,
+            recipient_token: self.recipient_token,
+            mint: self.mint,
+            escrow_vault: self.escrow_vault,
+
+            created_ts: self.created_ts,
+            start_ts: self.start_ts,
+            end_ts: self.end_ts,
+
+            period: self.period,
+            period_unit: PeriodUnit::Seconds,
+            cliff: self.cliff,
+            cliff_release_rate: self.cliff_release_rate,
+            cliff_amount: self.cliff_amount,
+            tge_release_rate: self.tge_release_rate,
+            tge_amount: self.tge_amount,
+
+            cancelable_by: CancelAuthority::Authority,
+            payer,
+            withdraw_authority: WithdrawAuthority::Anyone,
+            withdraw_delegate: Pubkey::default(),
+
+            kind: VestingKind::Schedule,
+            ..Vesting::default()
+        }
+    }
+}
+
+impl AccountSerialize for VestingV1 {}
+
+impl AccountDeserialize for VestingV1 {
+    fn try_deserialize(buf: &mut &[u8]) -> std::result::Result<Self, ProgramError> {
+        if buf.len() < 8 || buf[..8] != Vesting::discriminator() {
+            return Err(anchor_lang::__private::ErrorCode::AccountDiscriminatorMismatch.into());
+        }
+        Self::try_deserialize_unchecked(buf)
+    }
+
+    fn try_deserialize_unchecked(buf: &mut &[u8]) -> std::result::Result<Self, ProgramError> {
+        let mut data: &[u8] = &buf[8..];
+        AnchorDeserialize::deserialize(&mut data)
+            .map_err(|_| anchor_lang::__private::ErrorCode::AccountDidNotDeserialize.into())
+    }
+}
+
+impl Owner for VestingV1 {
+    fn owner() -> Pubkey {
+        crate::ID
+    }
+}
+
+// A struct holds the escrow and the merkle root of a merkle vesting pool.
+#[account]
+pub struct MerklePool {
+    /// Magic bytes, always fill the string "TMLK"(timelock).
+    pub magic: u32,
+    /// The merkle pool bump.
+    pub merkle_pool_bump: u8,
+    /// The escrow vault bump.
+    pub escrow_vault_bump: u8,
+    /// The merkle pool id.
+    pub pool_id: u64,
+    /// The merkle root of the (index, recipient, amount, schedule) leaves.
+    pub merkle_root: [u8; 32],
+
+    /// The sum of the amounts of the leaves, deposited at creation.
+    pub total_amount: u64,
+    /// The sum of the amounts of the claimed leaves.
+    pub claimed_amount: u64,
+
+    /// Pubkey of the token mint.
+    pub mint: Pubkey,
+    /// Pubkey of the escrow vault account holding the unclaimed tokens.
+    pub escrow_vault: Pubkey,
+
+    /// Timestamp when the pool was created.
+    pub created_ts: u64,
+    /// Timestamp after which the leaves can no longer be claimed, and the authority may claw back.
+    pub claim_deadline: u64,
+
+    /// Who is allowed to cancel the claimed vestings.
+    pub cancelable_by: CancelAuthority,
+    /// Who is allowed to withdraw the unlocked tokens of the claimed vestings.
+    pub withdraw_authority: WithdrawAuthority,
+
+    /// Pubkey of the account that paid the rent of the pool and its escrow vault.
+    pub payer: Pubkey,
+}
+
+impl Default for MerklePool {
+    fn default() -> MerklePool {
+        unsafe { std::mem::zeroed() }
+    }
+}
+
+// A struct records the claim of a merkle pool leaf, it is never closed so the leaf is not claimed again.
+#[account]
+#[derive(Default)]
+pub struct ClaimReceipt {
+    /// Magic bytes, always fill the string "TMLK"(timelock).
+    pub magic: u32,
+    /// The claim receipt bump.
+    pub claim_receipt_bump: u8,
+    /// Pubkey of the merkle pool of the leaf.
+    pub merkle_pool: Pubkey,
+    /// The index of the claimed leaf.
+    pub index: u64,
+    /// Pubkey of the vesting created by the claim.
+    pub vesting: Pubkey,
+    /// Timestamp when the leaf was claimed.
+    pub claimed_ts: u64,
+}
+
+///-------------------------------------
+/// Events
+///-------------------------------------
+
+// Triggered when initialize.
+#[event]
+pub struct InitializeEvent {
+    pub data: u64,
+    #[index]
+    pub status: String,
+}
+
+// Triggered when migrate config.
+#[event]
+pub struct MigrateConfigEvent {
+    pub data: u64,
+    #[index]
+    pub status: String,
+}
+
+// Triggered when migrate vesting.
+#[event]
+pub struct MigrateVestingEvent {
+    pub data: u64,
+    #[index]
+    pub status: String,
+}
+
+// Triggered when create vesting.
+#[event]
+pub struct CreateVestingEvent {
+    pub data: u64,
+    #[index]
+    pub status: String,
+}
+
+// Triggered when create vestings batch.
+#[event]
+pub struct CreateVestingsBatchEvent {
+    pub data: u64,
+    #[index]
+    pub status: String,
+    /// The number of entries of the batch.
+    pub count: u64,
+}
+
+// Triggered when create merkle pool.
+#[event]
+pub struct CreateMerklePoolEvent {
+    pub data: u64,
+    #[index]
+    pub status: String,
+}
+
+// Triggered when claim merkle vesting.
+#[event]
+pub struct ClaimMerkleVestingEvent {
+    pub data: u64,
+    #[index]
+    pub status: String,
+}
+
+// Triggered when clawback merkle pool.
+#[event]
+pub struct ClawbackMerklePoolEvent {
+    pub data: u64,
+    #[index]
+    pub status: String,
+}
+
+// Triggered when top up vesting.
+#[event]
+pub struct TopUpVestingEvent {
+    pub data: u64,
+    #[index]
+    pub status: String,
+    /// Whether the tokens were released at once.
+    pub release_immediately: bool,
+}
+
+// Triggered when amend schedule.
+#[event]
+pub struct AmendScheduleEvent {
+    pub data: u64,
+    #[index]
+    pub status: String,
+    /// The terms before the amendment.
+    pub old_terms: ScheduleTerms,
+    /// The terms after the amendment.
+    pub new_terms: ScheduleTerms,
+}
+
+// Triggered when withdraw.
+#[event]
+pub struct WithdrawEvent {
+    pub data: u64,
+    #[index]
+    pub status: String,
+}
+
+// Triggered when cancel.
+#[event]
+pub struct CancelEvent {
+    pub data: u64,
+    #[index]
+    pub status: String,
+    /// Amount already vested and settled to the recipient.
+    pub settled_amount: u64,
+    /// Unvested amount returned to the granter.
+    pub returned_amount: u64,
+}
+
+// Triggered when close vesting.
+#[event]
+pub struct CloseVestingEvent {
+    pub data: u64,
+    #[index]
+    pub status: String,
+}
+
+// Triggered when transfer vesting.
+#[event]
+pub struct TransferVestingEvent {
+    pub data: u64,
+    #[index]
+    pub status: String,
+    /// The previous recipient.
+    pub previous_recipient: Pubkey,
+    /// The new recipient.
+    pub recipient: Pubkey,
+}
+
+// Triggered when release milestone.
+#[event]
+pub struct ReleaseMilestoneEvent {
+    pub data: u64,
+    #[index]
+    pub status: String,
+    /// The index of the released tranche.
+    pub index: u8,
+}
+
+// Triggered when fund payment vault.
+#[event]
+pub struct FundPaymentVaultEvent {
+    pub data: u64,
+    #[index]
+    pub status: String,
+}
+
+// Triggered when withdraw payment vault.
+#[event]
+pub struct WithdrawPaymentVaultEvent {
+    pub data: u64,
+    #[index]
+    pub status: String,
+}
+
+// Triggered when propose authority.
+#[event]
+pub struct ProposeAuthorityEvent {
+    pub data: u64,
+    #[index]
+    pub status: String,
+    /// The current authority.
+    pub authority: Pubkey,
+    /// The proposed authority.
+    pub pending_authority: Pubkey,
+}
+
+// Triggered when accept authority.
+#[event]
+pub struct AcceptAuthorityEvent {
+    pub data: u64,
+    #[index]
+    pub status: String,
+    /// The previous authority.
+    pub previous_authority: Pubkey,
+    /// The new authority.
+    pub authority: Pubkey,
+}
+
+// Triggered when set roles.
+#[event]
+pub struct SetRolesEvent {
+    pub data: u64,
+    #[index]
+    pub status: String,
+    /// The new creator.
+    pub creator: Pubkey,
+    /// The new canceller.
+    pub canceller: Pubkey,
+    /// The new pauser.
+    pub pauser: Pubkey,
+}
+
+// Triggered when set transfer approval.
+#[event]
+pub struct SetTransferApprovalEvent {
+    pub data: u64,
+    #[index]
+    pub status: String,
+    /// Whether transfer_vesting must be co-signed by the authority.
+    pub required: bool,
+}
+
+// Triggered when pause or unpause.
+#[event]
+pub struct PauseEvent {
+    pub data: u64,
+    #[index]
+    pub status: String,
+    /// Whether the program is paused.
+    pub paused: bool,
+}
+
+impl Default for Vesting {
+    fn default() -> Vesting {
+        // Zero every field but the breakpoints, a zeroed Vec is not a valid value.
+        let mut vesting = std::mem::MaybeUninit::<Vesting>::zeroed();
+        unsafe {
+            std::ptr::addr_of_mut!((*vesting.as_mut_ptr()).breakpoints).write(Vec::new());
+            vesting.assume_init()
+        }
+    }
+}
+
+/// Returns a new vesting holding `total_amount` for the recipient, the caller sets how it unlocks.
+#[allow(clippy::too_many_arguments)]
+pub fn new_vesting(
+    total_amount: u64,
+    escrow_vault_bump: u8,
+    vesting_bump: u8,
+    vesting_id: u64,
+    vesting_name: [u8; 32],
+    investor_wallet_address: [u8; 64],
+    granter: Pubkey,
+    granter_token: Pubkey,
+    recipient: Pubkey,
+    recipient_token: Pubkey,
+    mint: Pubkey,
+    escrow_vault: Pubkey,
+    payer: Pubkey,
+    created_ts: u64,
+    cancelable_by: CancelAuthority,
+    withdraw_authority: WithdrawAuthority,
+    withdraw_delegate: Pubkey,
+) -> Vesting {
+    Vesting {
+        magic: 0x544D4C4B,
+        version: VESTING_VERSION,
+        escrow_vault_bump,
+        vesting_bump,
+        vesting_id,
+        vesting_name,
+        investor_wallet_address,
+
+        withdrawn_amount: 0,
+        remaining_amount: total_amount,
+        total_amount,
+
+        granter,
+        granter_token,
+
+        recipient,
+        original_recipient: recipient,
+        recipient_token,
+        mint,
+        escrow_vault,
+
+        created_ts,
+
+        cancelable_by,
+        withdraw_authority,
+        withdraw_delegate,
+        payer,
+        ..Vesting::default()
+    }
+}
+
+/// Hand the vesting over to `recipient`. The creator chose who may withdraw, so the withdraw
+/// authority is kept, but the delegate acted for the previous recipient and is dropped.
+pub fn transfer(vesting: &mut Vesting, recipient: Pubkey, recipient_token: Pubkey) {
+    vesting.recipient = recipient;
+    vesting.recipient_token = recipient_token;
+    if vesting.withdraw_authority == WithdrawAuthority::RecipientOrDelegate {
+        vesting.withdraw_authority = WithdrawAuthority::Recipient;
+    }
+    vesting.withdraw_delegate = Pubkey::default();
+}
+
+/// Returns whether `token` is the associated token address of `authority` for `mint`.
+fn is_associated_token(authority: &Pubkey, mint: &Pubkey, token: &Pubkey) -> bool {
+    associated_token::get_associated_token_address(authority, mint) == *token
+}
+
+/// Create the associated token account of `accounts.authority` if it does not exist yet, the
+/// caller verifies the address and emits its own event when it mismatches.
+fn create_associated_token_account<'info>(
+    cpi_program: AccountInfo<'info>,
+    accounts: Create<'info>,
+) -> ProgramResult {
+    if accounts.associated_token.data_is_empty() {
+        let cpi_ctx = CpiContext::new(cpi_program, accounts);
+        associated_token::create(cpi_ctx)?;
+    }
+
+    Ok(())
+}
+
+/// Transfer `amount` tokens out of the payment vault of `config`, signed by the payment vault PDA.
+fn transfer_from_payment_vault<'info>(
+    config: &Account<'info, Config>,
+    token_program: AccountInfo<'info>,
+    payment_vault: AccountInfo<'info>,
+    to: AccountInfo<'info>,
+    amount: u64,
+) -> ProgramResult {
+    let cpi_accounts = Transfer {
+        from: payment_vault.clone(),
+        to,
+        authority: payment_vault,
+    };
+
+    let seeds = &[config.to_account_info().key.as_ref(), &[config.payment_vault_bump]];
+    let signer = &[&seeds[..]];
+
+    let cpi_ctx = CpiContext::new(token_program, cpi_accounts).with_signer(signer);
+    token::transfer(cpi_ctx, amount)
+}
 
-    /// Token program.
-    #[account(address = token::ID)]
-    pub token_program: Program<'info, Token>,
+/// Hand `vesting` over to the authority of `accounts`, creating its associated token account if
+/// it does not exist yet.
+fn transfer_to_new_recipient<'info>(
+    vesting: &mut Account<'info, Vesting>,
+    cpi_program: AccountInfo<'info>,
+    accounts: Create<'info>,
+) -> ProgramResult {
+    let recipient = *accounts.authority.key;
+    let recipient_token = *accounts.associated_token.key;
 
-    /// Associated token program.
-    #[account(address = associated_token::ID)]
-    pub associated_token_program: Program<'info, AssociatedToken>,
+    // Verify that the new recipient's associated token address is correct.
+    if !is_associated_token(&recipient, accounts.mint.key, &recipient_token) {
+        emit!(TransferVestingEvent {
+            data: ErrorCode::InvalidAssociatedTokenAddress as u64,
+            status: "err".to_string(),
+            previous_recipient: vesting.recipient,
+            recipient,
+        });
+        msg!("new recipient tokens key not match:");
+        msg!("ctx.accounts.new_recipient_token.key: {}", recipient_token);
+        return Err(ErrorCode::InvalidAssociatedTokenAddress.into());
+    }
 
-    /// System program.
-    #[account(address = system_program::ID)]
-    pub system_program: Program<'info, System>,
+    // Create the new recipient's associated token account if needed.
+    create_associated_token_account(cpi_program, accounts)?;
 
-    ///Rent for rent exempt.
-    #[account(address = solana_program::sysvar::rent::ID)]
-    pub rent: Sysvar<'info, Rent>,
+    let previous_recipient = vesting.recipient;
+    transfer(vesting, recipient, recipient_token);
+
+    emit!(TransferVestingEvent {
+        data: 0,
+        status: "ok".to_string(),
+        previous_recipient,
+        recipient: vesting.recipient,
+    });
+
+    Ok(())
 }
 
-/* create_vesting context */
-// Accounts for create_vesting.
-#[derive(Accounts)]
-#[instruction(total_amount: u64, escrow_vault_bump: u8, vesting_bump: u8, vesting_id: u64)]
-pub struct CreateVesting<'info> {
+/// Withdraw `amount` unlocked tokens of `vesting` to `recipient_token`, u64::MAX withdraws everything available.
+fn withdraw_from_escrow<'info>(
+    vesting: &mut Account<'info, Vesting>,
+    escrow_vault: &Account<'info, TokenAccount>,
+    recipient_token: &Account<'info, TokenAccount>,
+    token_program: AccountInfo<'info>,
+    amount: u64,
+    now: u64,
+) -> ProgramResult {
+    // Check withdrawal amount validity.
+    if amount == 0 {
+        emit!(WithdrawEvent {
+            data: ErrorCode::InvalidWithdrawalAmount as u64,
+            status: "err".to_string(),
+        });
+        msg!("withdraw param amount illegal : {}", amount);
+        msg!("recipient_token : {}", recipient_token.key());
+        return Err(ErrorCode::InvalidWithdrawalAmount.into());
+    }
 
-    /// The account that must have permission to invoke this instruction.
-    #[account(mut)]
-    pub signer: Signer<'info>,
+    let available = available_for_withdrawal(vesting, now)?;
 
-    /// The payment vault token account.
-    #[account(
-        mut,
-        seeds = [config.to_account_info().key.as_ref()], bump = config.payment_vault_bump,
-        constraint = payment_vault.mint == config.mint @ErrorCode::InvalidMintMismatch,
-    )]
-    pub payment_vault: Account<'info, TokenAccount>,
+    if available == 0 {
+        emit!(WithdrawEvent {
+            data: ErrorCode::InsufficientWithdrawalAmount as u64,
+            status: "err".to_string(),
+        });
+        msg!("withdrawal amount illegal : {}", available);
+        msg!("recipient_token : {}", recipient_token.key());
+        return Err(ErrorCode::InsufficientWithdrawalAmount.into());
+    }
 
-    /// The account for saving configuration (PDA).
-    #[account(
-        seeds = [b"gyc_timelock".as_ref()],
-        bump = config.config_bump,
-        owner = id(),
-        constraint = config.authority == signer.key() @ErrorCode::Unauthorized,
-    )]
-    pub config: Box<Account<'info, Config>>,
+    // Withdraw exactly what is available at execution time.
+    let amount = if amount == u64::MAX { available } else { amount };
 
-    /// the recipient of main account
-    pub recipient: AccountInfo<'info>,
-    /// the recipient of token account
-    #[account(mut)]
-    pub recipient_token: AccountInfo<'info>,
+    if amount > available {
+        msg!("withdraw param amount is bigger than available :");
+        msg!("recipient_token : {}", recipient_token.key());
+        msg!("amount : {}", amount);
+        msg!("available : {}", available);
+        return Err(ErrorCode::InvalidWithdrawalAmount.into());
+    }
 
-    /// vesting account.
-    #[account(
-        init,
-        payer = signer,
-        seeds = [ vesting_id.to_string().as_ref(), recipient.key().as_ref()], bump = vesting_bump,
-        owner = id(),
-        rent_exempt = enforce,
-    )]
-    pub vesting: Box<Account<'info, Vesting>>,
+    // Transfer funds out.
+    let seeds = &[vesting.to_account_info().key.as_ref(), &[vesting.escrow_vault_bump]];
+    let signer = &[&seeds[..]];
+    let cpi_accounts = Transfer {
+        from: escrow_vault.to_account_info(),
+        to: recipient_token.to_account_info(),
+        authority: escrow_vault.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new(token_program, cpi_accounts).with_signer(signer);
+    token::transfer(cpi_ctx, amount)?;
 
-    /// escrow vault.
-    #[account(
-        init, payer = signer,
-        seeds = [vesting.to_account_info().key.as_ref()], bump = escrow_vault_bump,
-        owner = token_program.key(),
-        rent_exempt = enforce,
-        token::mint = mint,
-        token::authority = escrow_vault,
-    )]
-    pub escrow_vault: Account<'info, TokenAccount>,
+    // Record remaining amount.
+    vesting.remaining_amount = vesting
+        .remaining_amount
+        .checked_sub(amount)
+        .ok_or(ErrorCode::Overflow)?;
 
-    /// Token mint.
-    pub mint: Account<'info, Mint>,
+    // Record withdrawn amount.
+    vesting.withdrawn_amount = vesting
+        .withdrawn_amount
+        .checked_add(amount)
+        .ok_or(ErrorCode::Overflow)?;
 
-    /// Token program.
-    #[account(address = token::ID)]
-    pub token_program: Program<'info, Token>,
+    emit!(WithdrawEvent {
+        data: amount,
+        status: "ok".to_string(),
+    });
 
-    /// Associated token program.
-    #[account(address = associated_token::ID)]
-    pub associated_token_program: Program<'info, AssociatedToken>,
+    Ok(())
+}
 
-    /// System program.
-    #[account(address = system_program::ID)]
-    pub system_program: Program<'info, System>,
+/// Settle the vested tokens of `vesting` to `recipient_token`, return the rest to `granter_token`
+/// and close the escrow vault, its rent going back to `payer`.
+fn cancel_to_granter<'info>(
+    vesting: &Account<'info, Vesting>,
+    escrow_vault: &Account<'info, TokenAccount>,
+    recipient_token: &Account<'info, TokenAccount>,
+    granter_token: &Account<'info, TokenAccount>,
+    payer: &AccountInfo<'info>,
+    token_program: AccountInfo<'info>,
+    now: u64,
+) -> ProgramResult {
+    //Check the balance in the vault
+    let balance = escrow_vault.amount;
 
-    /// Clock represents network time.
-    #[account(address = solana_program::sysvar::clock::ID)]
-    pub clock: Sysvar<'info, Clock>,
+    // Tokens already released by the schedule belong to the recipient.
+    let settled = std::cmp::min(
+        available_for_withdrawal(vesting, now)?,
+        balance,
+    );
+    let remaining = balance - settled;
 
-    ///Rent for rent exempt.
-    #[account(address = solana_program::sysvar::rent::ID)]
-    pub rent: Sysvar<'info, Rent>,
+    let seeds = &[
+        vesting.to_account_info().key.as_ref(),
+        &[vesting.escrow_vault_bump],
+    ];
+    let signer = &[&seeds[..]];
+
+    if settled > 0 {
+        // Settle vested but unclaimed tokens to the recipient.
+        let cpi_accounts = Transfer {
+            from: escrow_vault.to_account_info(),
+            to: recipient_token.to_account_info(),
+            authority: escrow_vault.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(token_program.clone(), cpi_accounts).with_signer(signer);
+        token::transfer(cpi_ctx, settled)?;
+    }
+
+    if remaining > 0 {
+        // Return the unvested tokens to the granter.
+        let cpi_accounts = Transfer {
+            from: escrow_vault.to_account_info(),
+            to: granter_token.to_account_info(),
+            authority: escrow_vault.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(token_program.clone(), cpi_accounts).with_signer(signer);
+        token::transfer(cpi_ctx, remaining)?;
+    }
+
+    // Close escrow vault account, the rent goes back to the payer of the creation.
+    let cpi_accounts = CloseAccount {
+        account: escrow_vault.to_account_info(),
+        destination: payer.clone(),
+        authority: escrow_vault.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new(token_program, cpi_accounts).with_signer(signer);
+    token::close_account(cpi_ctx)?;
+
+    emit!(CancelEvent {
+        data: balance,
+        status: "ok".to_string(),
+        settled_amount: settled,
+        returned_amount: remaining,
+    });
+
+    Ok(())
 }
 
-/* withdraw context */
+/// Calculate the number of available withdrawals, never more than the remaining amount.
+pub fn available_for_withdrawal(vesting: &Vesting, current_ts: u64) -> Result<u64> {
+    let unlocked = unlocked_amount(vesting, current_ts)?;
 
-// Accounts for withdraw.
-#[derive(Accounts)]
-pub struct Withdraw<'info> {
-    /// the recipient of token account.
-    #[account(
-        mut,
-        constraint = recipient_token.mint == mint.key() @ErrorCode::InvalidMintMismatch,
-    )]
-    pub recipient_token: Account<'info, TokenAccount>,
+    Ok(std::cmp::min(
+        unlocked.saturating_sub(vesting.withdrawn_amount),
+        vesting.remaining_amount,
+    ))
+}
 
-    /// vesting account.
-    #[account(
-        mut,
-        owner = id() @ErrorCode::InvalidVestingOwner,
-        constraint = vesting.magic == 0x544D4C4B @ErrorCode::InvalidMagic,
-        constraint = vesting.escrow_vault == escrow_vault.key() @ErrorCode::InvalidEscrowVaultMismatch,
-        constraint = vesting.recipient_token == recipient_token.key() @ErrorCode::InvalidRecipientTokenMismatch,
-    )]
-    pub vesting: Box<Account<'info, Vesting>>,
+/// Returns the cumulative amount unlocked by the schedule at `current_ts`, regardless of withdrawals.
+pub fn unlocked_amount(vesting: &Vesting, current_ts: u64) -> Result<u64> {
+    // Top-ups released immediately are outside the schedule.
+    let scheduled_total = vesting
+        .total_amount
+        .checked_sub(vesting.immediate_amount)
+        .ok_or(ErrorCode::Overflow)?;
 
-    /// escrow vault.
-    #[account(
-        mut,
-        constraint = escrow_vault.mint == mint.key() @ErrorCode::InvalidMintMismatch,
-        seeds = [vesting.to_account_info().key.as_ref()],
-        bump = vesting.escrow_vault_bump,
-    )]
-    pub escrow_vault: Account<'info, TokenAccount>,
+    let scheduled_unlocked = match vesting.kind {
+        // Milestone tranches unlock once released, regardless of time.
+        VestingKind::Milestone => vesting
+            .milestones
+            .iter()
+            .filter(|milestone| milestone.released_ts != 0)
+            .try_fold(0u64, |unlocked, milestone| unlocked.checked_add(milestone.amount))
+            .ok_or(ErrorCode::Overflow)?,
+        VestingKind::Piecewise => piecewise_unlocked(vesting, scheduled_total, current_ts)?,
+        VestingKind::Schedule => schedule_unlocked(vesting, scheduled_total, current_ts)?,
+    };
 
-    /// Token mint.
-    #[account(address = vesting.mint @ErrorCode::InvalidMintMismatch,)]
-    pub mint: Account<'info, Mint>,
+    scheduled_unlocked
+        .checked_add(vesting.immediate_amount)
+        .ok_or_else(|| ErrorCode::Overflow.into())
+}
 
-    /// Token program.
-    #[account(address = token::ID)]
-    pub token_program: Program<'info, Token>,
+/// Returns the amount of `total` unlocked by the tge, cliff and linear schedule at `current_ts`.
+pub fn schedule_unlocked(vesting: &Vesting, total: u64, current_ts: u64) -> Result<u64> {
+    if current_ts >= vesting.end_ts {
+        return Ok(total);
+    }
 
-    /// Clock represents network time.
-    #[account(address = solana_program::sysvar::clock::ID)]
-    pub clock: Sysvar<'info, Clock>,
+    // Nothing is unlocked before the vesting starts.
+    if current_ts < vesting.start_ts {
+        return Ok(0);
+    }
+
+    // Only the tge amount is unlocked before the cliff.
+    let mut unlocked = vesting.tge_amount;
+    if vesting.cliff == 0 || current_ts >= vesting.cliff {
+        let (_, linear_unlocked) = linear_progress(vesting, total, current_ts)?;
+
+        unlocked = unlocked
+            .checked_add(vesting.cliff_amount)
+            .and_then(|unlocked| unlocked.checked_add(linear_unlocked))
+            .ok_or(ErrorCode::Overflow)?;
+    }
+
+    Ok(unlocked)
 }
 
-/* cancel context */
-// Accounts for cancel.
-#[derive(Accounts)]
-pub struct CancelVesting<'info> {
-    /// signer or granter of vesting.
-    #[account(mut)]
-    pub signer: Signer<'info>,
+/// Returns the elapsed periods and the amount unlocked linearly at `current_ts`, out of the
+/// linear part of `total`. The linear unlocking restarts from the last rebase, so a top-up
+/// unlocks over the remaining periods only.
+pub fn linear_progress(vesting: &Vesting, total: u64, current_ts: u64) -> Result<(u64, u64)> {
+    let linear = linear_amount(total, vesting.tge_amount, vesting.cliff_amount)?;
+    let linear_start_ts = linear_start(vesting.start_ts, vesting.cliff);
+    let total_periods = total_periods(
+        vesting.start_ts,
+        vesting.end_ts,
+        vesting.period,
+        vesting.cliff,
+        vesting.period_unit,
+    );
+    if current_ts >= vesting.end_ts {
+        return Ok((total_periods, linear));
+    }
+    if current_ts < linear_start_ts {
+        return Ok((0, 0));
+    }
 
-    /// The payment vault token account.
-    #[account(
-        mut,
-        seeds = [config.to_account_info().key.as_ref()], bump = config.payment_vault_bump,
-        constraint = payment_vault.mint == config.mint @ErrorCode::InvalidMintMismatch,
-    )]
-    pub payment_vault: Account<'info, TokenAccount>,
+    let elapsed_periods = std::cmp::min(
+        elapsed_periods(linear_start_ts, current_ts, vesting.period, vesting.period_unit),
+        total_periods,
+    );
 
-    /// The account for saving configuration (PDA).
-    #[account(
-        seeds = [b"gyc_timelock".as_ref()],
-        bump = config.config_bump,
-        owner = id(),
-        constraint = config.authority == signer.key() @ErrorCode::Unauthorized,
-    )]
-    pub config: Box<Account<'info, Config>>,
+    // rebase + (linear - rebase) * elapsed / remaining periods in u128, so the cumulative
+    // rounding never exceeds one base unit.
+    let rebase_periods = vesting.rebase_periods;
+    let rebase_unlocked = std::cmp::min(vesting.rebase_unlocked, linear);
+    let linear_unlocked = if elapsed_periods < rebase_periods {
+        (rebase_unlocked as u128 * elapsed_periods as u128 / rebase_periods as u128) as u64
+    } else if rebase_periods >= total_periods {
+        rebase_unlocked
+    } else {
+        rebase_unlocked
+            + ((linear - rebase_unlocked) as u128 * (elapsed_periods - rebase_periods) as u128
+                / (total_periods - rebase_periods) as u128) as u64
+    };
 
-    /// vesting.
-    #[account(
-        mut,
-        close = signer,
-        owner = id() @ErrorCode::InvalidVestingOwner,
-        constraint = vesting.magic == 0x544D4C4B @ErrorCode::InvalidMagic,
-        constraint = vesting.escrow_vault == escrow_vault.key() @ErrorCode::InvalidEscrowVaultMismatch,
-        constraint = vesting.granter == payment_vault.to_account_info().key() @ErrorCode::InvalidGranterMismatch,
-        constraint = vesting.granter_token == payment_vault.to_account_info().key() @ErrorCode::InvalidGranterTokenMismatch,
-    )]
-    pub vesting: Box<Account<'info, Vesting>>,
+    Ok((elapsed_periods, linear_unlocked))
+}
 
-    /// escrow vault.
-    #[account(
-        mut,
-        constraint = escrow_vault.mint == mint.key()  @ErrorCode::InvalidMintMismatch,
-        seeds = [vesting.to_account_info().key.as_ref()],
-        bump = vesting.escrow_vault_bump,
-    )]
-    pub escrow_vault: Account<'info, TokenAccount>,
+/// Add `amount` to the vesting at `current_ts`, either released immediately or unlocked
+/// pro-rata over the remaining periods of the schedule.
+pub fn top_up(vesting: &mut Vesting, amount: u64, release_immediately: bool, current_ts: u64) -> ProgramResult {
+    if !release_immediately {
+        let scheduled_total = vesting
+            .total_amount
+            .checked_sub(vesting.immediate_amount)
+            .ok_or(ErrorCode::Overflow)?;
+        let (elapsed_periods, linear_unlocked) = linear_progress(vesting, scheduled_total, current_ts)?;
+        vesting.rebase_periods = elapsed_periods;
+        vesting.rebase_unlocked = linear_unlocked;
+    } else {
+        vesting.immediate_amount = vesting
+            .immediate_amount
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+    }
 
-    /// Token mint.
-    #[account(address = vesting.mint @ErrorCode::InvalidMintMismatch,)]
-    pub mint: Account<'info, Mint>,
+    vesting.total_amount = vesting.total_amount.checked_add(amount).ok_or(ErrorCode::Overflow)?;
+    vesting.remaining_amount = vesting
+        .remaining_amount
+        .checked_add(amount)
+        .ok_or(ErrorCode::Overflow)?;
 
-    /// Token program.
-    #[account(address = token::ID)]
-    pub token_program: Program<'info, Token>,
+    Ok(())
 }
 
-// --------------------------------
-// PDA Structs
-// --------------------------------
+/// Check the schedule of a vesting of `total_amount` from `start_ts` with `terms`, the rates over
+/// `denominator`. The timestamps are checked against `now` when it is given.
+pub fn check_schedule(
+    total_amount: u64,
+    start_ts: u64,
+    period_unit: PeriodUnit,
+    terms: &ScheduleTerms,
+    denominator: u64,
+    now: Option<u64>,
+) -> std::result::Result<(), ErrorCode> {
+    if total_amount == 0 {
+        return Err(ErrorCode::InvalidDepositAmount);
+    }
+    if let Some(now) = now {
+        if !time_check(now, start_ts, terms.end_ts, terms.cliff) {
+            return Err(ErrorCode::InvalidSchedule);
+        }
+    }
+    // The end must be after the start, even when the timestamps are not checked against now.
+    if terms.end_ts <= start_ts {
+        return Err(ErrorCode::InvalidSchedule);
+    }
+    // The schedule must span more than one period.
+    if terms.period == 0 || total_periods(start_ts, terms.end_ts, terms.period, 0, period_unit) < 2 {
+        return Err(ErrorCode::InvalidPeriod);
+    }
+    if terms.tge_release_rate > denominator
+        || terms.cliff_release_rate > denominator
+        || terms.tge_release_rate + terms.cliff_release_rate > denominator
+    {
+        return Err(ErrorCode::InvalidReleaseRate);
+    }
+    if (terms.cliff != 0 && (terms.cliff < start_ts || terms.cliff > terms.end_ts))
+        || (terms.cliff == 0 && terms.cliff_release_rate != 0)
+    {
+        return Err(ErrorCode::InvalidCliffTime);
+    }
 
-// A struct controls vesting.
-#[account]
-pub struct Vesting {
-    /// Magic bytes, always fill the string "TMLK"(timelock).
-    pub magic: u32,
-    /// Contract version.
-    pub version: u32,
-    /// The escrow vault bump.
-    pub escrow_vault_bump: u8,
-    /// Vesting bump.
-    pub vesting_bump: u8,
-    /// The vesting id.
-    pub vesting_id: u64,
-    /// The vesting name.
-    pub vesting_name: [u8; 32],
-    /// The investor wallet address.
-    pub investor_wallet_address: [u8; 64],
+    Ok(())
+}
 
-    /// Amount of funds withdrawn.
-    pub withdrawn_amount: u64,
-    /// Remaining amount of the tokens in the escrow account.
-    pub remaining_amount: u64,
-    /// The starting balance of this vesting account, i.e., how much was
-    /// originally deposited.
-    pub total_amount: u64,
+/// Set the linear schedule of the vesting to `terms` from `start_ts`, the tge and cliff amounts
+/// taken from `total_amount`.
+pub fn set_schedule(
+    vesting: &mut Vesting,
+    total_amount: u64,
+    start_ts: u64,
+    period_unit: PeriodUnit,
+    terms: &ScheduleTerms,
+) {
+    let denominator = vesting.rate_denominator();
+    vesting.kind = VestingKind::Schedule;
+    vesting.start_ts = start_ts;
+    vesting.end_ts = terms.end_ts;
+
+    vesting.period = terms.period;
+    vesting.period_unit = period_unit;
+
+    vesting.cliff = terms.cliff;
+    vesting.cliff_release_rate = terms.cliff_release_rate;
+    vesting.cliff_amount = rate_amount(total_amount, terms.cliff_release_rate, denominator);
+
+    vesting.tge_release_rate = terms.tge_release_rate;
+    vesting.tge_amount = rate_amount(total_amount, terms.tge_release_rate, denominator);
+}
+
+/// Amend the linear schedule of the vesting with `terms` at `current_ts`. The amount unlocked
+/// so far is kept and the rest unlocks over the remaining periods of the new schedule.
+pub fn amend(
+    vesting: &mut Vesting,
+    terms: &ScheduleTerms,
+    current_ts: u64,
+) -> std::result::Result<(), ErrorCode> {
+    check_schedule(
+        vesting.total_amount,
+        vesting.start_ts,
+        vesting.period_unit,
+        terms,
+        vesting.rate_denominator(),
+        None,
+    )?;
+
+    let scheduled_total = vesting
+        .total_amount
+        .checked_sub(vesting.immediate_amount)
+        .ok_or(ErrorCode::Overflow)?;
+    let vested = schedule_unlocked(vesting, scheduled_total, current_ts).map_err(|_| ErrorCode::Overflow)?;
+
+    // Work on a copy, so the vesting is left untouched when the amendment is rejected.
+    let mut amended = vesting.clone();
+    set_schedule(&mut amended, scheduled_total, vesting.start_ts, vesting.period_unit, terms);
+
+    // Carry the vested linear amount over, so the new schedule continues from it.
+    amended.rebase_periods = 0;
+    amended.rebase_unlocked = 0;
+    let (elapsed_periods, _) =
+        linear_progress(&amended, scheduled_total, current_ts).map_err(|_| ErrorCode::Overflow)?;
+    if elapsed_periods != 0 {
+        amended.rebase_periods = elapsed_periods;
+        amended.rebase_unlocked = vested
+            .saturating_sub(amended.tge_amount)
+            .saturating_sub(amended.cliff_amount);
+    }
+
+    // Never take back what is already vested.
+    let amended_vested =
+        schedule_unlocked(&amended, scheduled_total, current_ts).map_err(|_| ErrorCode::Overflow)?;
+    if amended_vested < vested {
+        return Err(ErrorCode::AmendmentReducesVested);
+    }
+
+    *vesting = amended;
+
+    Ok(())
+}
+
+/// Check the recipient, recipient token, vesting and escrow vault accounts of a batch entry.
+fn check_batch_accounts(
+    program_id: &Pubkey,
+    mint: &Pubkey,
+    entry: &BatchEntry,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let (recipient, recipient_token, vesting, escrow_vault) =
+        (&accounts[0], &accounts[1], &accounts[2], &accounts[3]);
+
+    if *recipient.key != entry.recipient {
+        return Err(ErrorCode::InvalidBatch.into());
+    }
+    if *recipient_token.key != associated_token::get_associated_token_address(recipient.key, mint) {
+        return Err(ErrorCode::InvalidAssociatedTokenAddress.into());
+    }
+
+    let vesting_key = Pubkey::create_program_address(
+        &[
+            entry.vesting_id.to_string().as_bytes(),
+            recipient.key.as_ref(),
+            mint.as_ref(),
+            &[entry.vesting_bump],
+        ],
+        program_id,
+    )
+    .map_err(|_| ErrorCode::InvalidProgramAddress)?;
+    let escrow_vault_key =
+        Pubkey::create_program_address(&[vesting.key.as_ref(), &[entry.escrow_vault_bump]], program_id)
+            .map_err(|_| ErrorCode::InvalidProgramAddress)?;
+    if *vesting.key != vesting_key || *escrow_vault.key != escrow_vault_key {
+        return Err(ErrorCode::InvalidProgramAddress.into());
+    }
+
+    // The vesting and escrow vault are created by the batch, they must not exist yet.
+    if !vesting.data_is_empty() || !escrow_vault.data_is_empty() {
+        return Err(ErrorCode::InvalidBatch.into());
+    }
 
-    /// Pubkey of the granter main account (signer).
-    pub granter: Pubkey,
-    /// Pubkey of the granter token account.
-    pub granter_token: Pubkey,
-    /// Pubkey of the recipient main account.
-    pub recipient: Pubkey,
-    /// Pubkey of the recipient token account.
-    pub recipient_token: Pubkey,
-    /// Pubkey of the token mint.
-    pub mint: Pubkey,
-    /// Pubkey of the escrow vault account holding the locked tokens.
-    pub escrow_vault: Pubkey,
+    Ok(())
+}
 
-    /// Timestamp when stream was created.
-    pub created_ts: u64,
-    /// Timestamp when the tokens start vesting.
-    pub start_ts: u64,
-    /// Timestamp when all tokens are fully vested.
-    pub end_ts: u64,
-    /// Internal billing time.
-    pub accounting_ts: u64,
-    /// Timestamp of the last withdrawal.
-    pub last_withdrawn_at: u64,
+/// Returns the hash of a merkle pool leaf, prefixed so that a leaf never passes as an inner node.
+pub fn merkle_leaf(leaf: &MerkleLeaf) -> [u8; 32] {
+    keccak::hashv(&[&[MERKLE_LEAF_PREFIX], &leaf.try_to_vec().unwrap()[..]]).0
+}
 
-    /// Time step (period) in seconds per which the vesting occurs.
-    pub period: u64,
-    /// Vesting contract "cliff" timestamp.
-    pub cliff: u64,
-    /// The rate of amount unlocked at the "cliff" timestamp.
-    pub cliff_release_rate: u64,
-    /// Amount unlocked at the "cliff" timestamp.
-    pub cliff_amount: u64,
-    /// The rate of amount unlocked at TGE.
-    pub tge_release_rate: u64,
-    /// Amount unlocked at TGE.
-    pub tge_amount: u64,
-    /// Amount to be unlocked per time during linear unlocking.
-    pub periodic_unlock_amount: u64,
+/// Verify that `leaf` belongs to `root`, the pair of each level is hashed in sorted order.
+pub fn verify_merkle_proof(proof: &[[u8; 32]], root: &[u8; 32], leaf: &[u8; 32]) -> bool {
+    let mut node = *leaf;
+    for sibling in proof.iter() {
+        node = if node <= *sibling {
+            keccak::hashv(&[&[MERKLE_NODE_PREFIX], &node, sibling]).0
+        } else {
+            keccak::hashv(&[&[MERKLE_NODE_PREFIX], sibling, &node]).0
+        };
+    }
+
+    node == *root
 }
 
-// A struct controls Config.
-#[account]
-pub struct Config {
-    /// The PDA bump of config account.
-    pub config_bump: u8,
+/// Returns the amount of `total` released by `rate` over `denominator`.
+pub fn rate_amount(total: u64, rate: u64, denominator: u64) -> u64 {
+    (total as u128 * rate as u128 / denominator as u128) as u64
+}
 
-    /// The PDA bump of payment vault token account.
-    pub payment_vault_bump: u8,
+/// Returns the amount of `total` unlocked by the breakpoints of a piecewise schedule at `current_ts`.
+pub fn piecewise_unlocked(vesting: &Vesting, total: u64, current_ts: u64) -> Result<u64> {
+    let breakpoints = &vesting.breakpoints;
+    let index = match breakpoints.iter().rposition(|breakpoint| breakpoint.ts <= current_ts) {
+        Some(index) => index,
+        None => return Ok(0),
+    };
 
-    /// The payment vault token account (PDA).
-    pub payment_vault: Pubkey,
+    // Everything is unlocked at the last breakpoint.
+    if index + 1 == breakpoints.len() {
+        return Ok(total);
+    }
 
-    /// The account that have permission to invoke create_vesting and cancel instruction instruction.
-    pub authority: Pubkey,
+    let (from, to) = (breakpoints[index], breakpoints[index + 1]);
+    let mut rate = from.rate;
+    if vesting.interpolation == Interpolation::Linear {
+        let duration = to.ts.checked_sub(from.ts).ok_or(ErrorCode::Overflow)?;
+        let grown = (to.rate.saturating_sub(from.rate) as u128 * (current_ts - from.ts) as u128)
+            .checked_div(duration as u128)
+            .unwrap_or(0);
+        rate = rate.checked_add(grown as u64).ok_or(ErrorCode::Overflow)?;
+    }
 
-    /// token mint.
-    pub mint: Pubkey,
+    Ok(bps_amount(total, rate))
 }
 
-impl Default for Config {
-    fn default() -> Config {
-        unsafe { std::mem::zeroed() }
+/// Whether the breakpoints are a valid piecewise schedule, i.e. strictly increasing timestamps,
+/// non-decreasing rates and everything unlocked at the last breakpoint.
+pub fn valid_breakpoints(breakpoints: &[Breakpoint]) -> bool {
+    match breakpoints.last() {
+        Some(last) if breakpoints.len() <= MAX_BREAKPOINTS && last.rate == BPS_DENOMINATOR => breakpoints
+            .windows(2)
+            .all(|pair| pair[0].ts < pair[1].ts && pair[0].rate <= pair[1].rate),
+        _ => false,
     }
 }
 
-///-------------------------------------
-/// Events
-///-------------------------------------
+/// Returns the amount of `total` released by `rate` basis points.
+pub fn bps_amount(total: u64, rate: u64) -> u64 {
+    rate_amount(total, rate, BPS_DENOMINATOR)
+}
 
-// Triggered when initialize.
-#[event]
-pub struct InitializeEvent {
-    pub data: u64,
-    #[index]
-    pub status: String,
+/// Returns the amount unlocked linearly, i.e. the remainder after the tge and cliff amounts.
+pub fn linear_amount(total: u64, tge_amount: u64, cliff_amount: u64) -> Result<u64> {
+    total
+        .checked_sub(tge_amount)
+        .and_then(|amount| amount.checked_sub(cliff_amount))
+        .ok_or_else(|| ErrorCode::Overflow.into())
 }
 
-// Triggered when create vesting.
-#[event]
-pub struct CreateVestingEvent {
-    pub data: u64,
-    #[index]
-    pub status: String,
+/// Returns the number of periods of linear unlocking, a trailing partial period counts as one.
+pub fn total_periods(start_ts: u64, end_ts: u64, period: u64, cliff: u64, unit: PeriodUnit) -> u64 {
+    let linear_start_ts = linear_start(start_ts, cliff);
+    if period == 0 || end_ts <= linear_start_ts {
+        return 0;
+    }
+
+    let whole_periods = elapsed_periods(linear_start_ts, end_ts, period, unit);
+    if period_start(linear_start_ts, whole_periods, period, unit) < end_ts {
+        whole_periods + 1
+    } else {
+        whole_periods
+    }
 }
 
-// Triggered when withdraw.
-#[event]
-pub struct WithdrawEvent {
-    pub data: u64,
-    #[index]
-    pub status: String,
+/// Returns the number of whole periods elapsed from `from_ts` to `to_ts`.
+pub fn elapsed_periods(from_ts: u64, to_ts: u64, period: u64, unit: PeriodUnit) -> u64 {
+    match unit.months() {
+        None => to_ts.saturating_sub(from_ts).checked_div(period).unwrap_or(0),
+        Some(months) => elapsed_months(from_ts, to_ts)
+            .checked_div(period.saturating_mul(months))
+            .unwrap_or(0),
+    }
 }
 
-// Triggered when cancel.
-#[event]
-pub struct CancelEvent {
-    pub data: u64,
-    #[index]
-    pub status: String,
+/// Returns the timestamp at which the period `index` starts, counted from `from_ts`.
+pub fn period_start(from_ts: u64, index: u64, period: u64, unit: PeriodUnit) -> u64 {
+    match unit.months() {
+        None => from_ts.saturating_add(index.saturating_mul(period)),
+        Some(months) => add_months(from_ts, index.saturating_mul(period).saturating_mul(months)),
+    }
 }
 
-impl Default for Vesting {
-    fn default() -> Vesting {
-        unsafe { std::mem::zeroed() }
+/// Returns the timestamp `months` calendar months after `ts`, on the same day of month and time
+/// of day, clamped to the last day of shorter months.
+pub fn add_months(ts: u64, months: u64) -> u64 {
+    let (year, month, day) = civil_from_days((ts / SECONDS_PER_DAY) as i64);
+    let total_months = year as i128 * 12 + (month as i128 - 1) + months as i128;
+    let year = total_months.div_euclid(12);
+    let month = (total_months.rem_euclid(12) + 1) as u32;
+
+    // Beyond the range of u64 timestamps.
+    if year > MAX_CIVIL_YEAR {
+        return u64::MAX;
     }
+
+    let day = std::cmp::min(day, days_in_month(year as i64, month));
+    let days = days_from_civil(year as i64, month, day) as u128;
+    let ts = days * SECONDS_PER_DAY as u128 + (ts % SECONDS_PER_DAY) as u128;
+    std::cmp::min(ts, u64::MAX as u128) as u64
 }
 
-/// Calculate the number of available withdrawals.
-pub fn available_for_withdrawal(vesting: &Vesting, current_ts: u64) -> u64 {
-    if current_ts >= vesting.end_ts {
-        return vesting.remaining_amount;
+/// Returns the number of whole calendar months elapsed from `from_ts` to `to_ts`.
+pub fn elapsed_months(from_ts: u64, to_ts: u64) -> u64 {
+    if to_ts <= from_ts {
+        return 0;
     }
 
-    let interval = current_ts - vesting.start_ts;
-    let unlocked = interval.checked_div(vesting.period).unwrap() * vesting.periodic_unlock_amount;
+    let (from_year, from_month, _) = civil_from_days((from_ts / SECONDS_PER_DAY) as i64);
+    let (to_year, to_month, _) = civil_from_days((to_ts / SECONDS_PER_DAY) as i64);
+    let months = ((to_year - from_year) * 12 + to_month as i64 - from_month as i64) as u64;
 
-    let available = unlocked + vesting.tge_amount - vesting.withdrawn_amount;
+    // The last month is not complete before its anniversary.
+    if add_months(from_ts, months) > to_ts {
+        months - 1
+    } else {
+        months
+    }
+}
+
+/// Returns the (year, month, day) of the given days since the unix epoch, in the proleptic Gregorian calendar.
+pub fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+/// Returns the days since the unix epoch of the given (year, month, day), in the proleptic Gregorian calendar.
+pub fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = if month > 2 { month - 3 } else { month + 9 } as i64;
+    let doy = (153 * mp + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// Returns the number of days of the given month.
+pub fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
 
-    available
+/// Returns the timestamp at which linear unlocking begins, i.e. the cliff if one is set.
+pub fn linear_start(start_ts: u64, cliff: u64) -> u64 {
+    if cliff > start_ts {
+        cliff
+    } else {
+        start_ts
+    }
 }
 
 /// Do a sanity check with given Unix timestamps.
@@ -802,7 +4843,7 @@ pub enum ErrorCode {
     InvalidTimestamp,
     #[msg("The number of vesting periods must be greater than zero.")]
     InvalidPeriod,
-    #[msg("The release rate of vesting must be less than 100")]
+    #[msg("The release rate of vesting must not exceed 100%")]
     InvalidReleaseRate,
     #[msg("The cliff time must be less than vesting time.")]
     InvalidCliffTime,
@@ -844,4 +4885,691 @@ pub enum ErrorCode {
     InvalidTokenAuthorityMismatch,
     #[msg("Invalid Withdrawal amount is zero.")]
     InsufficientWithdrawalAmount,
+    #[msg("The program is paused.")]
+    Paused,
+    #[msg("The vesting still holds tokens.")]
+    VestingNotEmpty,
+    #[msg("The payer account mismatch.")]
+    InvalidPayerMismatch,
+    #[msg("Invalid milestone given.")]
+    InvalidMilestone,
+    #[msg("The milestone has already been released.")]
+    MilestoneAlreadyReleased,
+    #[msg("Invalid breakpoints given.")]
+    InvalidBreakpoints,
+    #[msg("The vesting is not in the layout of the first version.")]
+    InvalidVestingLayout,
+    #[msg("The operation is not supported by the kind of vesting.")]
+    InvalidVestingKind,
+    #[msg("The amendment would reduce the vested amount.")]
+    AmendmentReducesVested,
+    #[msg("Invalid batch given.")]
+    InvalidBatch,
+    #[msg("Insufficient payment vault balance.")]
+    InsufficientPaymentVault,
+    #[msg("Invalid merkle proof given.")]
+    InvalidMerkleProof,
+    #[msg("The claim deadline of the merkle pool has passed.")]
+    ClaimDeadlinePassed,
+    #[msg("The claim deadline of the merkle pool has not been reached.")]
+    ClaimDeadlineNotReached,
+    #[msg("The merkle pool does not hold enough unclaimed tokens.")]
+    MerklePoolExhausted,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const START: u64 = 1_000;
+    const PERIOD: u64 = 100;
+    const CLIFF: u64 = START + 3 * PERIOD;
+
+    fn schedule(end_ts: u64, cliff: u64, total_amount: u64, tge_amount: u64, cliff_amount: u64) -> Vesting {
+        Vesting {
+            start_ts: START,
+            end_ts,
+            period: PERIOD,
+            cliff,
+            total_amount,
+            remaining_amount: total_amount,
+            tge_amount,
+            cliff_amount,
+            ..Vesting::default()
+        }
+    }
+
+    fn withdraw(vesting: &mut Vesting, now: u64) -> u64 {
+        let amount = available_for_withdrawal(vesting, now).unwrap();
+        vesting.remaining_amount -= amount;
+        vesting.withdrawn_amount += amount;
+        amount
+    }
+
+    // 1000 tokens over 10 periods, 10% at tge and 20% at the cliff after 3 periods.
+    fn cliff_schedule() -> Vesting {
+        schedule(START + 10 * PERIOD, CLIFF, 1_000, 100, 200)
+    }
+
+    #[test]
+    fn only_tge_unlocked_just_before_cliff() {
+        let vesting = cliff_schedule();
+        assert_eq!(available_for_withdrawal(&vesting, START).unwrap(), 100);
+        assert_eq!(available_for_withdrawal(&vesting, CLIFF - 1).unwrap(), 100);
+    }
+
+    #[test]
+    fn cliff_amount_unlocked_exactly_at_cliff() {
+        let vesting = cliff_schedule();
+        assert_eq!(available_for_withdrawal(&vesting, CLIFF).unwrap(), 300);
+    }
+
+    #[test]
+    fn linear_unlock_starts_after_cliff() {
+        let vesting = cliff_schedule();
+        assert_eq!(available_for_withdrawal(&vesting, CLIFF + 1).unwrap(), 300);
+        assert_eq!(available_for_withdrawal(&vesting, CLIFF + PERIOD - 1).unwrap(), 300);
+        assert_eq!(available_for_withdrawal(&vesting, CLIFF + PERIOD).unwrap(), 400);
+    }
+
+    #[test]
+    fn last_partial_period_unlocks_at_end() {
+        // 10 whole periods and a trailing half period, which counts as an eleventh.
+        let end = START + 10 * PERIOD + PERIOD / 2;
+        let mut vesting = cliff_schedule();
+        vesting.end_ts = end;
+        vesting.cliff = 0;
+        vesting.tge_amount = 0;
+        vesting.cliff_amount = 0;
+        assert_eq!(available_for_withdrawal(&vesting, START + 10 * PERIOD).unwrap(), 909);
+        assert_eq!(available_for_withdrawal(&vesting, end - 1).unwrap(), 909);
+        assert_eq!(available_for_withdrawal(&vesting, end).unwrap(), 1_000);
+    }
+
+    #[test]
+    fn rounding_never_unlocks_more_than_total() {
+        for total_amount in [1, 2, 7, 997, 1_001, u64::MAX / 100] {
+            let mut vesting = cliff_schedule();
+            vesting.end_ts = START + 3 * PERIOD + 1;
+            vesting.cliff = 0;
+            vesting.total_amount = total_amount;
+            vesting.remaining_amount = total_amount;
+            vesting.tge_amount = rate_amount(total_amount, 7, PERCENT_DENOMINATOR);
+            vesting.cliff_amount = 0;
+
+            let mut last = 0;
+            for now in (START..vesting.end_ts + PERIOD).step_by(7) {
+                let unlocked = available_for_withdrawal(&vesting, now).unwrap();
+                assert!(unlocked >= last);
+                assert!(unlocked <= total_amount);
+                last = unlocked;
+            }
+            assert_eq!(last, total_amount);
+        }
+    }
+
+    #[test]
+    fn config_v1_decodes_with_the_config_discriminator() {
+        let config = ConfigV1 {
+            config_bump: 254,
+            payment_vault_bump: 253,
+            payment_vault: Pubkey::new_unique(),
+            authority: Pubkey::new_unique(),
+            mint: Pubkey::new_unique(),
+        };
+        let mut data = Config::discriminator().to_vec();
+        data.extend(config.try_to_vec().unwrap());
+
+        let decoded = ConfigV1::try_deserialize(&mut &data[..]).unwrap();
+        assert_eq!(decoded.payment_vault_bump, 253);
+        assert_eq!(decoded.authority, config.authority);
+        assert_eq!(decoded.mint, config.mint);
+
+        data[..8].copy_from_slice(&Vesting::discriminator());
+        assert!(ConfigV1::try_deserialize(&mut &data[..]).is_err());
+    }
+
+    #[test]
+    fn transfer_drops_the_withdraw_delegate() {
+        let mut vesting = cliff_schedule();
+        let (recipient, delegate) = (Pubkey::new_unique(), Pubkey::new_unique());
+        vesting.withdraw_authority = WithdrawAuthority::RecipientOrDelegate;
+        vesting.withdraw_delegate = delegate;
+
+        transfer(&mut vesting, recipient, Pubkey::new_unique());
+        assert_eq!(vesting.recipient, recipient);
+        assert_eq!(vesting.withdraw_delegate, Pubkey::default());
+        assert!(!vesting.withdraw_authority.allows(&delegate, &vesting.recipient, &vesting.withdraw_delegate));
+        assert!(vesting.withdraw_authority.allows(&recipient, &vesting.recipient, &vesting.withdraw_delegate));
+    }
+
+    #[test]
+    fn transfer_keeps_anyone_withdraw_authority() {
+        let mut vesting = cliff_schedule();
+        vesting.withdraw_authority = WithdrawAuthority::Anyone;
+
+        transfer(&mut vesting, Pubkey::new_unique(), Pubkey::new_unique());
+        assert!(vesting.withdraw_authority == WithdrawAuthority::Anyone);
+        assert!(vesting.withdraw_authority.allows(&Pubkey::new_unique(), &vesting.recipient, &vesting.withdraw_delegate));
+    }
+
+    #[test]
+    fn nothing_unlocked_before_start() {
+        let vesting = schedule(START + 10 * PERIOD, 0, 1_000, 100, 0);
+        assert_eq!(unlocked_amount(&vesting, 0).unwrap(), 0);
+        assert_eq!(unlocked_amount(&vesting, START - 1).unwrap(), 0);
+        assert_eq!(unlocked_amount(&vesting, START).unwrap(), 100);
+    }
+
+    #[test]
+    fn unlocks_at_each_period_boundary() {
+        let vesting = schedule(START + 10 * PERIOD, 0, 1_000, 0, 0);
+        for k in 1..10 {
+            let boundary = START + k * PERIOD;
+            assert_eq!(unlocked_amount(&vesting, boundary - 1).unwrap(), (k - 1) * 100);
+            assert_eq!(unlocked_amount(&vesting, boundary).unwrap(), k * 100);
+            assert_eq!(unlocked_amount(&vesting, boundary + 1).unwrap(), k * 100);
+        }
+        assert_eq!(unlocked_amount(&vesting, START + 10 * PERIOD - 1).unwrap(), 900);
+        assert_eq!(unlocked_amount(&vesting, START + 10 * PERIOD).unwrap(), 1_000);
+    }
+
+    #[test]
+    fn withdraw_around_boundaries_is_idempotent() {
+        let end = START + 10 * PERIOD;
+        let mut vesting = schedule(end, 0, 1_000, 0, 0);
+        for k in 1..=10 {
+            let boundary = START + k * PERIOD;
+            assert_eq!(withdraw(&mut vesting, boundary - 1), 0);
+            assert_eq!(withdraw(&mut vesting, boundary), 100);
+            assert_eq!(withdraw(&mut vesting, boundary), 0);
+            assert_eq!(withdraw(&mut vesting, boundary + 1), 0);
+        }
+        assert_eq!(vesting.withdrawn_amount, 1_000);
+        assert_eq!(vesting.remaining_amount, 0);
+        assert_eq!(available_for_withdrawal(&vesting, end + PERIOD).unwrap(), 0);
+    }
+
+    #[test]
+    fn late_withdraw_catches_up() {
+        let mut vesting = schedule(START + 10 * PERIOD, 0, 1_000, 0, 0);
+        assert_eq!(withdraw(&mut vesting, START + PERIOD + 1), 100);
+        assert_eq!(withdraw(&mut vesting, START + 4 * PERIOD - 1), 200);
+        assert_eq!(withdraw(&mut vesting, START + 4 * PERIOD), 100);
+        assert_eq!(vesting.withdrawn_amount, unlocked_amount(&vesting, START + 4 * PERIOD).unwrap());
+    }
+
+    #[test]
+    fn tge_and_cliff_unlock_before_linear() {
+        let cliff = START + 5 * PERIOD;
+        let vesting = schedule(START + 15 * PERIOD, cliff, 1_000, 100, 200);
+        assert_eq!(unlocked_amount(&vesting, cliff - 1).unwrap(), 100);
+        assert_eq!(unlocked_amount(&vesting, cliff).unwrap(), 300);
+        assert_eq!(unlocked_amount(&vesting, cliff + PERIOD - 1).unwrap(), 300);
+        assert_eq!(unlocked_amount(&vesting, cliff + PERIOD).unwrap(), 370);
+        assert_eq!(unlocked_amount(&vesting, START + 15 * PERIOD).unwrap(), 1_000);
+    }
+
+    #[test]
+    fn trailing_partial_period_unlocks_at_end() {
+        let end = START + 2 * PERIOD + PERIOD / 2;
+        let vesting = schedule(end, 0, 1_000, 0, 0);
+        assert_eq!(unlocked_amount(&vesting, START + PERIOD).unwrap(), 333);
+        assert_eq!(unlocked_amount(&vesting, START + 2 * PERIOD).unwrap(), 666);
+        assert_eq!(unlocked_amount(&vesting, end - 1).unwrap(), 666);
+        assert_eq!(unlocked_amount(&vesting, end).unwrap(), 1_000);
+    }
+
+    #[test]
+    fn zero_period_does_not_panic() {
+        let mut vesting = schedule(START + 10 * PERIOD, 0, 1_000, 100, 0);
+        vesting.period = 0;
+        assert_eq!(unlocked_amount(&vesting, START + PERIOD).unwrap(), 100);
+        assert_eq!(unlocked_amount(&vesting, START + 10 * PERIOD).unwrap(), 1_000);
+    }
+
+    #[test]
+    fn milestone_unlocks_released_tranches() {
+        let mut vesting = schedule(START + 10 * PERIOD, 0, 1_000, 0, 0);
+        vesting.kind = VestingKind::Milestone;
+        vesting.milestones[0] = Milestone { rate: 2_500, amount: 250, released_ts: 0 };
+        vesting.milestones[1] = Milestone { rate: 7_500, amount: 750, released_ts: 0 };
+        assert_eq!(unlocked_amount(&vesting, START + 20 * PERIOD).unwrap(), 0);
+
+        vesting.milestones[1].released_ts = START;
+        assert_eq!(unlocked_amount(&vesting, 0).unwrap(), 750);
+        assert_eq!(withdraw(&mut vesting, 0), 750);
+
+        vesting.milestones[0].released_ts = START;
+        assert_eq!(withdraw(&mut vesting, 0), 250);
+        assert_eq!(vesting.remaining_amount, 0);
+    }
+
+    fn piecewise(interpolation: Interpolation, breakpoints: &[(u64, u64)]) -> Vesting {
+        let mut vesting = schedule(0, 0, 10_000, 0, 0);
+        vesting.kind = VestingKind::Piecewise;
+        vesting.interpolation = interpolation;
+        vesting.breakpoints = breakpoints
+            .iter()
+            .map(|(ts, rate)| Breakpoint { ts: *ts, rate: *rate })
+            .collect();
+        vesting
+    }
+
+    #[test]
+    fn piecewise_step_holds_rate_until_next_breakpoint() {
+        // 10% at TGE, nothing during the cliff, 15% at the next two breakpoints, then the rest.
+        let vesting = piecewise(
+            Interpolation::Step,
+            &[
+                (START, 1_000),
+                (START + 6 * PERIOD, 1_000),
+                (START + 9 * PERIOD, 2_500),
+                (START + 12 * PERIOD, 4_000),
+                (START + 13 * PERIOD, 10_000),
+            ],
+        );
+        assert_eq!(unlocked_amount(&vesting, START - 1).unwrap(), 0);
+        assert_eq!(unlocked_amount(&vesting, START).unwrap(), 1_000);
+        assert_eq!(unlocked_amount(&vesting, START + 9 * PERIOD - 1).unwrap(), 1_000);
+        assert_eq!(unlocked_amount(&vesting, START + 9 * PERIOD).unwrap(), 2_500);
+        assert_eq!(unlocked_amount(&vesting, START + 13 * PERIOD - 1).unwrap(), 4_000);
+        assert_eq!(unlocked_amount(&vesting, START + 13 * PERIOD).unwrap(), 10_000);
+    }
+
+    #[test]
+    fn piecewise_linear_interpolates_between_breakpoints() {
+        let vesting = piecewise(Interpolation::Linear, &[(START, 1_000), (START + 10 * PERIOD, 10_000)]);
+        assert_eq!(unlocked_amount(&vesting, START).unwrap(), 1_000);
+        assert_eq!(unlocked_amount(&vesting, START + 5 * PERIOD).unwrap(), 5_500);
+        assert_eq!(unlocked_amount(&vesting, START + 10 * PERIOD).unwrap(), 10_000);
+    }
+
+    #[test]
+    fn breakpoints_must_be_monotonic() {
+        let breakpoint = |ts, rate| Breakpoint { ts, rate };
+        assert!(valid_breakpoints(&[breakpoint(START, 10_000)]));
+        assert!(valid_breakpoints(&[breakpoint(START, 0), breakpoint(START + 1, 10_000)]));
+        assert!(!valid_breakpoints(&[]));
+        assert!(!valid_breakpoints(&[breakpoint(START, 0), breakpoint(START + 1, 9_999)]));
+        assert!(!valid_breakpoints(&[breakpoint(START, 0), breakpoint(START, 10_000)]));
+        assert!(!valid_breakpoints(&[
+            breakpoint(START, 5_000),
+            breakpoint(START + 1, 4_000),
+            breakpoint(START + 2, 10_000),
+        ]));
+    }
+
+    #[test]
+    fn add_months_clamps_to_short_months() {
+        // 2024-01-31, 2024-02-29, 2024-03-31 and 2024-04-30.
+        let jan_31 = 1_706_659_200;
+        assert_eq!(add_months(jan_31, 1), 1_709_164_800);
+        assert_eq!(add_months(jan_31, 2), 1_711_843_200);
+        assert_eq!(add_months(jan_31, 3), 1_714_435_200);
+        assert_eq!(add_months(jan_31 + 3_600, 1), 1_709_164_800 + 3_600);
+        assert_eq!(elapsed_months(jan_31, 1_709_164_800 - 1), 0);
+        assert_eq!(elapsed_months(jan_31, 1_709_164_800), 1);
+        assert_eq!(add_months(u64::MAX, u64::MAX), u64::MAX);
+    }
+
+    #[test]
+    fn calendar_months_unlock_on_the_first() {
+        // Monthly over 2024 from 2024-01-01 to 2025-01-01.
+        let (jan_1, feb_1, apr_1) = (1_704_067_200, 1_706_745_600, 1_711_929_600);
+        let next_jan_1 = 1_735_689_600;
+        let mut vesting = schedule(next_jan_1, 0, 1_200, 0, 0);
+        vesting.start_ts = jan_1;
+        vesting.period = 1;
+        vesting.period_unit = PeriodUnit::Month;
+        assert_eq!(total_periods(jan_1, next_jan_1, 1, 0, PeriodUnit::Month), 12);
+        assert_eq!(unlocked_amount(&vesting, feb_1 - 1).unwrap(), 0);
+        assert_eq!(unlocked_amount(&vesting, feb_1).unwrap(), 100);
+        assert_eq!(unlocked_amount(&vesting, apr_1).unwrap(), 300);
+        assert_eq!(unlocked_amount(&vesting, next_jan_1).unwrap(), 1_200);
+
+        vesting.period_unit = PeriodUnit::Quarter;
+        assert_eq!(unlocked_amount(&vesting, apr_1 - 1).unwrap(), 0);
+        assert_eq!(unlocked_amount(&vesting, apr_1).unwrap(), 300);
+    }
+
+    #[test]
+    fn release_rates_keep_the_unit_of_their_version() {
+        let mut vesting = Vesting { version: 1, ..Vesting::default() };
+        assert_eq!(rate_amount(1_000, 20, vesting.rate_denominator()), 200);
+
+        vesting.version = VESTING_VERSION;
+        assert_eq!(rate_amount(1_000, 250, vesting.rate_denominator()), 25);
+        assert_eq!(rate_amount(1_000_000, 1_275, vesting.rate_denominator()), 127_500);
+    }
+
+    #[test]
+    fn vesting_v1_migrates_with_percent_rates() {
+        let recipient = Pubkey::new_unique();
+        let escrow_vault = Pubkey::new_unique();
+
+        // A vesting of the first version, 1000 tokens with 10% at tge and 20% at the cliff.
+        let mut data = Vesting::discriminator().to_vec();
+        data.extend(0x544D4C4Bu32.to_le_bytes());
+        data.extend(1u32.to_le_bytes());
+        data.extend([255, 254]);
+        data.extend(7u64.to_le_bytes());
+        data.extend([0; 32 + 64]);
+        for amount in [100u64, 900, 1_000] {
+            data.extend(amount.to_le_bytes());
+        }
+        let (granter, recipient_token, mint) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        for key in [granter, granter, recipient, recipient_token, mint, escrow_vault] {
+            data.extend(key.to_bytes());
+        }
+        for value in [START, START, START + 10 * PERIOD, START, START, PERIOD, CLIFF, 20, 200, 10, 100, 100] {
+            data.extend(value.to_le_bytes());
+        }
+        assert_eq!(data.len(), 8 + VESTING_V1_SIZE);
+
+        let payment_vault = Pubkey::new_unique();
+        let old = VestingV1::try_deserialize(&mut &data[..]).unwrap();
+        let vesting = old.migrate(payment_vault, Pubkey::new_unique());
+        assert_eq!(vesting.rate_denominator(), PERCENT_DENOMINATOR);
+        assert_eq!(vesting.recipient, recipient);
+        assert_eq!(vesting.original_recipient, recipient);
+        assert_eq!(vesting.escrow_vault, escrow_vault);
+        assert_eq!(vesting.granter_token, payment_vault);
+
+        assert_eq!(unlocked_amount(&vesting, CLIFF - 1).unwrap(), 100);
+        assert_eq!(unlocked_amount(&vesting, CLIFF).unwrap(), 300);
+        assert_eq!(available_for_withdrawal(&vesting, CLIFF).unwrap(), 200);
+        assert_eq!(unlocked_amount(&vesting, START + 10 * PERIOD).unwrap(), 1_000);
+        assert_eq!(available_for_withdrawal(&vesting, START + 10 * PERIOD).unwrap(), 900);
+
+        // The current layout is longer, so the account grows on migration.
+        assert!(8 + vesting.try_to_vec().unwrap().len() > data.len());
+    }
+
+    #[test]
+    fn pro_rata_top_up_unlocks_over_remaining_periods() {
+        let mut vesting = schedule(START + 10 * PERIOD, 0, 1_000, 0, 0);
+        assert_eq!(withdraw(&mut vesting, START + 4 * PERIOD), 400);
+
+        top_up(&mut vesting, 600, false, START + 4 * PERIOD + 1).unwrap();
+        assert_eq!(vesting.total_amount, 1_600);
+        assert_eq!(unlocked_amount(&vesting, START + 4 * PERIOD + 1).unwrap(), 400);
+        assert_eq!(unlocked_amount(&vesting, START + 5 * PERIOD).unwrap(), 600);
+        assert_eq!(unlocked_amount(&vesting, START + 10 * PERIOD - 1).unwrap(), 1_400);
+        assert_eq!(withdraw(&mut vesting, START + 10 * PERIOD), 1_200);
+        assert_eq!(vesting.remaining_amount, 0);
+    }
+
+    #[test]
+    fn immediate_top_up_is_available_at_once() {
+        let mut vesting = schedule(START + 10 * PERIOD, 0, 1_000, 0, 0);
+        top_up(&mut vesting, 100, true, START - 1).unwrap();
+        assert_eq!(available_for_withdrawal(&vesting, START - 1).unwrap(), 100);
+        assert_eq!(unlocked_amount(&vesting, START + PERIOD).unwrap(), 200);
+        assert_eq!(unlocked_amount(&vesting, START + 10 * PERIOD).unwrap(), 1_100);
+    }
+
+    fn terms(end_ts: u64, cliff: u64, cliff_release_rate: u64, tge_release_rate: u64) -> ScheduleTerms {
+        ScheduleTerms {
+            end_ts,
+            period: PERIOD,
+            cliff,
+            cliff_release_rate,
+            tge_release_rate,
+        }
+    }
+
+    #[test]
+    fn amend_extends_schedule_from_vested_amount() {
+        let mut vesting = schedule(START + 10 * PERIOD, 0, 1_000, 0, 0);
+        vesting.version = VESTING_VERSION;
+        let now = START + 4 * PERIOD;
+
+        // Push the end out by 6 periods, the remaining 600 unlock over 12 periods.
+        amend(&mut vesting, &terms(START + 16 * PERIOD, 0, 0, 0), now).unwrap();
+        assert_eq!(unlocked_amount(&vesting, now).unwrap(), 400);
+        assert_eq!(unlocked_amount(&vesting, START + 6 * PERIOD).unwrap(), 500);
+        assert_eq!(unlocked_amount(&vesting, START + 16 * PERIOD - 1).unwrap(), 950);
+        assert_eq!(unlocked_amount(&vesting, START + 16 * PERIOD).unwrap(), 1_000);
+    }
+
+    #[test]
+    fn amend_rebases_on_the_amended_amounts() {
+        let mut vesting = schedule(START + 10 * PERIOD, 0, 1_000, 0, 0);
+        vesting.version = VESTING_VERSION;
+        let now = START + 4 * PERIOD;
+
+        // A 10% tge counts towards the 400 vested, the linear 900 continue from 300.
+        amend(&mut vesting, &terms(START + 16 * PERIOD, 0, 0, 1_000), now).unwrap();
+        assert_eq!(vesting.tge_amount, 100);
+        assert_eq!(vesting.rebase_periods, 4);
+        assert_eq!(vesting.rebase_unlocked, 300);
+        assert_eq!(unlocked_amount(&vesting, now).unwrap(), 400);
+        assert_eq!(unlocked_amount(&vesting, START + 10 * PERIOD).unwrap(), 700);
+        assert_eq!(unlocked_amount(&vesting, START + 16 * PERIOD).unwrap(), 1_000);
+    }
+
+    #[test]
+    fn check_schedule_rejects_an_empty_vesting() {
+        let terms = terms(START + 10 * PERIOD, 0, 0, 0);
+        let check = |total_amount| {
+            check_schedule(total_amount, START, PeriodUnit::Seconds, &terms, BPS_DENOMINATOR, None)
+        };
+        assert!(check(1_000).is_ok());
+        assert!(matches!(check(0), Err(ErrorCode::InvalidDepositAmount)));
+    }
+
+    #[test]
+    fn amend_never_reduces_vested_amount() {
+        let mut vesting = schedule(START + 10 * PERIOD, 0, 1_000, 0, 0);
+        vesting.version = VESTING_VERSION;
+        let now = START + 4 * PERIOD;
+
+        // Moving the cliff past now would lock the 400 vested so far.
+        let err = amend(&mut vesting, &terms(START + 10 * PERIOD, START + 5 * PERIOD, 0, 0), now);
+        assert!(matches!(err, Err(ErrorCode::AmendmentReducesVested)));
+
+        // Invalid terms are rejected.
+        let err = amend(&mut vesting, &terms(START, 0, 0, 0), now);
+        assert!(matches!(err, Err(ErrorCode::InvalidSchedule)));
+        let err = amend(&mut vesting, &terms(START + 10 * PERIOD, 0, 0, 10_001), now);
+        assert!(matches!(err, Err(ErrorCode::InvalidReleaseRate)));
+        let err = amend(&mut vesting, &terms(START + 10 * PERIOD, 0, 100, 0), now);
+        assert!(matches!(err, Err(ErrorCode::InvalidCliffTime)));
+
+        // Shortening the schedule unlocks faster.
+        amend(&mut vesting, &terms(START + 5 * PERIOD, 0, 0, 2_000), now).unwrap();
+        assert!(unlocked_amount(&vesting, now).unwrap() >= 400);
+        assert_eq!(unlocked_amount(&vesting, START + 5 * PERIOD).unwrap(), 1_000);
+    }
+
+    fn entry(total_amount: u64, terms: ScheduleTerms) -> BatchEntry {
+        BatchEntry {
+            recipient: Pubkey::default(),
+            total_amount,
+            vesting_id: 1,
+            vesting_bump: 255,
+            escrow_vault_bump: 255,
+            start_ts: START,
+            period_unit: PeriodUnit::Seconds,
+            terms,
+        }
+    }
+
+    fn check_entry(entry: &BatchEntry, now: Option<u64>) -> std::result::Result<(), ErrorCode> {
+        check_schedule(entry.total_amount, entry.start_ts, entry.period_unit, &entry.terms, BPS_DENOMINATOR, now)
+    }
+
+    #[test]
+    fn batch_entries_are_checked_like_create_vesting() {
+        let valid = entry(1_000, terms(START + 10 * PERIOD, START + 5 * PERIOD, 2_000, 1_000));
+        assert!(check_entry(&valid, Some(START - 1)).is_ok());
+        assert!(matches!(check_entry(&valid, Some(START)), Err(ErrorCode::InvalidSchedule)));
+        assert!(check_entry(&valid, None).is_ok());
+
+        let invalid = [
+            (entry(0, valid.terms), ErrorCode::InvalidDepositAmount),
+            (entry(1_000, terms(START, 0, 0, 0)), ErrorCode::InvalidSchedule),
+            (entry(1_000, terms(START + PERIOD, 0, 0, 0)), ErrorCode::InvalidPeriod),
+            (entry(1_000, terms(START + 10 * PERIOD, 0, 0, 10_001)), ErrorCode::InvalidReleaseRate),
+            (entry(1_000, terms(START + 10 * PERIOD, 0, 100, 0)), ErrorCode::InvalidCliffTime),
+        ];
+        for (entry, code) in invalid {
+            assert_eq!(check_entry(&entry, None).unwrap_err() as u64, code as u64);
+        }
+    }
+
+    #[test]
+    fn batch_schedule_unlocks_like_create_vesting() {
+        let cliff = START + 5 * PERIOD;
+        let entry = entry(1_000, terms(START + 15 * PERIOD, cliff, 2_000, 1_000));
+        let mut vesting = Vesting { version: VESTING_VERSION, ..Vesting::default() };
+        set_schedule(&mut vesting, 1_000, entry.start_ts, entry.period_unit, &entry.terms);
+        vesting.total_amount = 1_000;
+        vesting.remaining_amount = 1_000;
+        assert_eq!(vesting.tge_amount, 100);
+        assert_eq!(vesting.cliff_amount, 200);
+        assert_eq!(unlocked_amount(&vesting, START).unwrap(), 100);
+        assert_eq!(unlocked_amount(&vesting, cliff).unwrap(), 300);
+        assert_eq!(unlocked_amount(&vesting, START + 15 * PERIOD).unwrap(), 1_000);
+        assert_eq!(available_for_withdrawal(&vesting, START + 15 * PERIOD).unwrap(), 1_000);
+    }
+
+    /// The size of a create_vestings_batch transaction of `count` entries of distinct recipients.
+    fn batch_transaction_size(count: usize) -> usize {
+        use anchor_lang::InstructionData;
+        use solana_program::instruction::{AccountMeta, Instruction};
+        use solana_program::message::Message;
+
+        let signer = Pubkey::new_unique();
+        let mut accounts = crate::accounts::CreateVestingsBatch {
+            signer,
+            payment_vault: Pubkey::new_unique(),
+            config: Pubkey::new_unique(),
+            mint: Pubkey::new_unique(),
+            token_program: token::ID,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+            clock: solana_program::sysvar::clock::ID,
+            rent: solana_program::sysvar::rent::ID,
+        }
+        .to_account_metas(None);
+        for _ in 0..count {
+            accounts.push(AccountMeta::new_readonly(Pubkey::new_unique(), false));
+            accounts.push(AccountMeta::new(Pubkey::new_unique(), false));
+            accounts.push(AccountMeta::new(Pubkey::new_unique(), false));
+            accounts.push(AccountMeta::new(Pubkey::new_unique(), false));
+        }
+        let data = crate::instruction::CreateVestingsBatch {
+            entries: vec![entry(1_000, terms(START + 10 * PERIOD, 0, 0, 0)); count],
+            bypass_timestamp_check: false,
+            cancelable_by: CancelAuthority::Authority,
+            withdraw_authority: WithdrawAuthority::Recipient,
+        }
+        .data();
+
+        let message = Message::new(&[Instruction::new_with_bytes(crate::ID, &data, accounts)], Some(&signer));
+        1 + 64 * message.header.num_required_signatures as usize + message.serialize().len()
+    }
+
+    #[test]
+    fn batch_of_max_entries_fits_in_a_transaction() {
+        // The packet size limit of a transaction.
+        const MAX_TRANSACTION_SIZE: usize = 1232;
+        assert!(batch_transaction_size(MAX_BATCH_ENTRIES) <= MAX_TRANSACTION_SIZE);
+        assert!(batch_transaction_size(MAX_BATCH_ENTRIES + 1) > MAX_TRANSACTION_SIZE);
+    }
+
+    fn leaf(index: u64) -> [u8; 32] {
+        merkle_leaf(&MerkleLeaf {
+            index,
+            recipient: Pubkey::default(),
+            total_amount: 1_000 * (index + 1),
+            start_ts: START,
+            period_unit: PeriodUnit::Seconds,
+            terms: terms(START + 10 * PERIOD, 0, 0, 0),
+        })
+    }
+
+    fn node(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+        if a <= b {
+            keccak::hashv(&[&[MERKLE_NODE_PREFIX], a, b]).0
+        } else {
+            keccak::hashv(&[&[MERKLE_NODE_PREFIX], b, a]).0
+        }
+    }
+
+    #[test]
+    fn merkle_proof_verifies_each_leaf() {
+        let leaves = [leaf(0), leaf(1), leaf(2)];
+        let inner = node(&leaves[0], &leaves[1]);
+        let root = node(&inner, &leaves[2]);
+
+        assert!(verify_merkle_proof(&[leaves[1], leaves[2]], &root, &leaves[0]));
+        assert!(verify_merkle_proof(&[leaves[0], leaves[2]], &root, &leaves[1]));
+        assert!(verify_merkle_proof(&[inner], &root, &leaves[2]));
+
+        // A different leaf, a truncated proof or a wrong root is rejected.
+        assert!(!verify_merkle_proof(&[leaves[1], leaves[2]], &root, &leaf(3)));
+        assert!(!verify_merkle_proof(&[leaves[1]], &root, &leaves[0]));
+        assert!(!verify_merkle_proof(&[leaves[1], leaves[2]], &inner, &leaves[0]));
+        assert_ne!(leaf(0), leaf(1));
+    }
+
+    /// Xorshift generator, so the property test is reproducible without extra dependencies.
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        /// Arbitrary value, biased towards the edges of the u64 range.
+        fn value(&mut self) -> u64 {
+            match self.next() % 8 {
+                0 => 0,
+                1 => u64::MAX,
+                2 => u64::MAX - self.next() % 1_000,
+                3 => self.next() % 1_000,
+                4 => self.next() % 100_000,
+                _ => self.next(),
+            }
+        }
+    }
+
+    #[test]
+    fn arbitrary_vesting_never_panics_nor_exceeds_remaining() {
+        let mut rng = Rng(0x544D4C4B);
+        for _ in 0..200_000 {
+            let vesting = Vesting {
+                start_ts: rng.value(),
+                end_ts: rng.value(),
+                period: rng.value(),
+                cliff: rng.value(),
+                total_amount: rng.value(),
+                remaining_amount: rng.value(),
+                withdrawn_amount: rng.value(),
+                tge_amount: rng.value(),
+                cliff_amount: rng.value(),
+                immediate_amount: rng.value(),
+                rebase_periods: rng.value(),
+                rebase_unlocked: rng.value(),
+                period_unit: match rng.next() % 4 {
+                    0 => PeriodUnit::Seconds,
+                    1 => PeriodUnit::Month,
+                    2 => PeriodUnit::Quarter,
+                    _ => PeriodUnit::Year,
+                },
+                ..Vesting::default()
+            };
+            let now = rng.value();
+
+            if let Ok(available) = available_for_withdrawal(&vesting, now) {
+                assert!(available <= vesting.remaining_amount);
+            }
+        }
+    }
 }
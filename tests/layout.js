@@ -26,15 +26,27 @@ const VestingDataLayout = BufferLayout.struct([
     BufferLayout.blob(8, "createTs"),
     BufferLayout.blob(8, "startTs"),
     BufferLayout.blob(8, "endTs"),
-    BufferLayout.blob(8, "accountingTs"),
-    BufferLayout.blob(8, "lastWithdrawnAt"),
     BufferLayout.blob(8, "period"),
+    BufferLayout.blob(1, "periodUnit"),
     BufferLayout.blob(8, "cliff"),
     BufferLayout.blob(8, "cliffReleaseRate"),
     BufferLayout.blob(8, "cliffAmount"),
     BufferLayout.blob(8, "tgeReleaseRate"),
     BufferLayout.blob(8, "tgeAmount"),
-    BufferLayout.blob(8, "periodicUnlockAmount"),
+    BufferLayout.blob(1, "cancelableBy"),
+    BufferLayout.blob(32, "payer"),
+    BufferLayout.blob(1, "withdrawAuthority"),
+    BufferLayout.blob(32, "withdrawDelegate"),
+    BufferLayout.blob(32, "originalRecipient"),
+    BufferLayout.blob(1, "kind"),
+    BufferLayout.blob(32, "milestoneOracle"),
+    BufferLayout.blob(8 * 24, "milestones"),
+    BufferLayout.blob(1, "interpolation"),
+    BufferLayout.blob(8, "immediateAmount"),
+    BufferLayout.blob(8, "rebasePeriods"),
+    BufferLayout.blob(8, "rebaseUnlocked"),
+    BufferLayout.blob(1, "selfFunded"),
+    BufferLayout.u32("breakpointsLength"),
 ]);
 
 function decode_vesting_data(buf) {
@@ -59,15 +71,27 @@ function decode_vesting_data(buf) {
         createTs: new BN(raw.createTs, LE),
         startTs: new BN(raw.startTs, LE),
         endTs: new BN(raw.endTs, LE),
-        accountingTs: new BN(raw.accountingTs, LE),
-        lastWithdrawnAt: new BN(raw.lastWithdrawnAt, LE),
         period: new BN(raw.period, LE),
+        periodUnit: raw.periodUnit.readUInt8(),
         cliff: new BN(raw.cliff, LE),
         cliffReleaseRate: new BN(raw.cliffReleaseRate, LE),
         cliffAmount: new BN(raw.cliffAmount, LE),
         tgeReleaseRate: new BN(raw.tgeReleaseRate, LE),
         tgeAmount: new BN(raw.tgeAmount, LE),
-        periodicUnlockAmount: new BN(raw.periodicUnlockAmount, LE),
+        cancelableBy: raw.cancelableBy.readUInt8(),
+        payer: new PublicKey(raw.payer),
+        withdrawAuthority: raw.withdrawAuthority.readUInt8(),
+        withdrawDelegate: new PublicKey(raw.withdrawDelegate),
+        originalRecipient: new PublicKey(raw.originalRecipient),
+        kind: raw.kind.readUInt8(),
+        milestoneOracle: new PublicKey(raw.milestoneOracle),
+        milestones: raw.milestones,
+        interpolation: raw.interpolation.readUInt8(),
+        immediateAmount: new BN(raw.immediateAmount, LE),
+        rebasePeriods: new BN(raw.rebasePeriods, LE),
+        rebaseUnlocked: new BN(raw.rebaseUnlocked, LE),
+        selfFunded: raw.selfFunded.readUInt8() != 0,
+        breakpointsLength: raw.breakpointsLength,
     }
 }
 
@@ -3,6 +3,7 @@ const anchor = require('@project-serum/anchor');
 const common = require('@project-serum/common');
 const nacl = require('tweetnacl');
 nacl.util = require('tweetnacl-util');
+const { keccak_256 } = require('js-sha3');
 
 const {
     TOKEN_PROGRAM_ID,
@@ -125,7 +126,7 @@ describe('spl-token-timelock', () => {
         console.log("mint: ", mint);
 
         [config, configBump] = await PublicKey.findProgramAddress(
-            [Buffer.from("gyc_timelock")],
+            [Buffer.from("gyc_timelock"), mint.toBuffer()],
             program.programId
         );
 
@@ -135,10 +136,11 @@ describe('spl-token-timelock', () => {
         );
 
         [vesting, vestingBump] = await PublicKey.findProgramAddress(
-            // [vestingId.toString(), recipient.toBuffer()],
+            // [vestingId.toString(), recipient.toBuffer(), mint.toBuffer()],
             [
                 Buffer.from(String(vestingId)),
-                recipient.toBuffer()
+                recipient.toBuffer(),
+                mint.toBuffer()
             ],
             program.programId
         );
@@ -216,6 +218,202 @@ paymentVaultBump: ${paymentVaultBump}
         console.log(`PaymentVault Token Amount: ${_paymentVaultData.amount}`);
     });
 
+    it("Withdraw and fund payment vault", async () => {
+
+        console.log(`Withdraw and fund payment vault: `);
+
+        const granterToken = await Token.getAssociatedTokenAddress(
+            ASSOCIATED_TOKEN_PROGRAM_ID,
+            TOKEN_PROGRAM_ID,
+            mint,
+            granter.publicKey
+        );
+
+        if (!(await program.provider.connection.getAccountInfo(granterToken))) {
+            await createAssociatedTokenAccount(
+                provider,
+                mint,
+                granterToken,
+                granter.publicKey,
+                granter.publicKey,
+                granter.payer
+            );
+        }
+
+        const amount = new BN(100 * LAMPORTS_PER_SOL);
+
+        const oldPaymentVaultAmount = common.token.parseTokenAccountData(
+            (await program.provider.connection.getAccountInfo(paymentVault)).data
+        ).amount;
+
+        // Move tokens from the payment vault back to treasury.
+        await program.rpc.withdrawPaymentVault(
+            amount, {
+            accounts: {
+                signer: granter.publicKey,
+                destinationToken: granterToken,
+                paymentVault: paymentVault,
+                config: config,
+                mint: mint,
+                tokenProgram: TOKEN_PROGRAM_ID,
+            },
+            signers: [granter.payer]
+        });
+
+        const withdrawnPaymentVaultAmount = common.token.parseTokenAccountData(
+            (await program.provider.connection.getAccountInfo(paymentVault)).data
+        ).amount;
+
+        assert.ok(new BN(oldPaymentVaultAmount - withdrawnPaymentVaultAmount).eq(amount));
+
+        // And fund them back.
+        await program.rpc.fundPaymentVault(
+            amount, {
+            accounts: {
+                signer: granter.publicKey,
+                sourceToken: granterToken,
+                paymentVault: paymentVault,
+                config: config,
+                mint: mint,
+                tokenProgram: TOKEN_PROGRAM_ID,
+            },
+            signers: [granter.payer]
+        });
+
+        const fundedPaymentVaultAmount = common.token.parseTokenAccountData(
+            (await program.provider.connection.getAccountInfo(paymentVault)).data
+        ).amount;
+
+        assert.ok(fundedPaymentVaultAmount === oldPaymentVaultAmount);
+    });
+
+    it("Rotate authority", async () => {
+
+        console.log(`Rotate authority: `);
+
+        const newAuthority = Keypair.generate();
+
+        // Hand over to the new authority.
+        await program.rpc.proposeAuthority(
+            newAuthority.publicKey, {
+            accounts: {
+                signer: granter.publicKey,
+                config: config,
+            },
+            signers: [granter.payer]
+        });
+
+        await program.rpc.acceptAuthority({
+            accounts: {
+                signer: newAuthority.publicKey,
+                config: config,
+            },
+            signers: [newAuthority]
+        });
+
+        let configAccount = await program.account.config.fetch(config);
+        assert.ok(configAccount.authority.equals(newAuthority.publicKey));
+
+        // And hand it back.
+        await program.rpc.proposeAuthority(
+            granter.publicKey, {
+            accounts: {
+                signer: newAuthority.publicKey,
+                config: config,
+            },
+            signers: [newAuthority]
+        });
+
+        await program.rpc.acceptAuthority({
+            accounts: {
+                signer: granter.publicKey,
+                config: config,
+            },
+            signers: [granter.payer]
+        });
+
+        configAccount = await program.account.config.fetch(config);
+        assert.ok(configAccount.authority.equals(granter.publicKey));
+        assert.ok(configAccount.pendingAuthority.equals(PublicKey.default));
+    });
+
+    it("Set roles", async () => {
+
+        console.log(`Set roles: `);
+
+        await program.rpc.setRoles(
+            granter.publicKey,
+            granter.publicKey,
+            granter.publicKey, {
+            accounts: {
+                signer: granter.publicKey,
+                config: config,
+            },
+            signers: [granter.payer]
+        });
+
+        const configAccount = await program.account.config.fetch(config);
+        assert.ok(configAccount.creator.equals(granter.publicKey));
+        assert.ok(configAccount.canceller.equals(granter.publicKey));
+        assert.ok(configAccount.pauser.equals(granter.publicKey));
+    });
+
+    it("Pause and unpause", async () => {
+
+        console.log(`Pause and unpause: `);
+
+        await program.rpc.pause({
+            accounts: {
+                signer: granter.publicKey,
+                config: config,
+            },
+            signers: [granter.payer]
+        });
+
+        let configAccount = await program.account.config.fetch(config);
+        assert.ok(configAccount.paused);
+
+        await program.rpc.unpause({
+            accounts: {
+                signer: granter.publicKey,
+                config: config,
+            },
+            signers: [granter.payer]
+        });
+
+        configAccount = await program.account.config.fetch(config);
+        assert.ok(!configAccount.paused);
+    });
+
+    it("Set transfer approval", async () => {
+
+        console.log(`Set transfer approval: `);
+
+        await program.rpc.setTransferApproval(
+            true, {
+            accounts: {
+                signer: granter.publicKey,
+                config: config,
+            },
+            signers: [granter.payer]
+        });
+
+        let configAccount = await program.account.config.fetch(config);
+        assert.ok(configAccount.transferRequiresApproval);
+
+        await program.rpc.setTransferApproval(
+            false, {
+            accounts: {
+                signer: granter.publicKey,
+                config: config,
+            },
+            signers: [granter.payer]
+        });
+
+        configAccount = await program.account.config.fetch(config);
+        assert.ok(!configAccount.transferRequiresApproval);
+    });
+
     it("Create vesting", async () => {
 
         console.log(`Create vesting: `);
@@ -243,11 +441,16 @@ paymentVaultBump: ${paymentVaultBump}
             period,
             cliff,
             new BN(0),
-            new BN(20),
-            true, {
+            new BN(2000),
+            true,
+            { authority: {} },
+            { anyone: {} },
+            PublicKey.default,
+            { seconds: {} }, {
             accounts: {
                 signer: granter.publicKey,
                 paymentVault: paymentVault,
+                granterToken: paymentVault,
                 config: config,
                 recipient: recipient,
                 recipientToken: recipientToken,
@@ -358,14 +561,16 @@ escrowVault: ${escrowVault.toBase58()}`);
         const tx = await program.rpc.withdraw(
             withdrawAmount, {
             accounts: {
+                signer: granter.publicKey,
                 recipientToken: recipientToken,
                 vesting: vesting,
+                config: config,
                 escrowVault: escrowVault,
                 mint: mint,
                 tokenProgram: TOKEN_PROGRAM_ID,
                 clock: anchor.web3.SYSVAR_CLOCK_PUBKEY
             },
-            signers: []
+            signers: [granter.payer]
         }
         );
 
@@ -425,6 +630,1048 @@ ${vesting_data.escrowVault.toBase58()}
         await program.removeEventListener(listener);
     });
 
+    it("Close vesting", async () => {
+
+        console.log(`Close vesting: `);
+
+        // The vesting still holds tokens, so it can not be closed yet.
+        try {
+            await program.rpc.closeVesting({
+                accounts: {
+                    payer: granter.publicKey,
+                    vesting: vesting,
+                    escrowVault: escrowVault,
+                    tokenProgram: TOKEN_PROGRAM_ID,
+                },
+                signers: []
+            });
+            assert.fail("close vesting should fail while tokens remain");
+        } catch (err) {
+            assert.equal(err.msg, "The vesting still holds tokens.");
+        }
+
+        const vestingAccount = await program.account.vesting.fetch(vesting);
+        assert.ok(vestingAccount.payer.equals(granter.publicKey));
+    });
+
+    it("Withdraw all", async () => {
+
+        console.log(`Withdraw all: `);
+
+        // The schedule has ended, so everything left in the escrow vault is available.
+        const oldEscrowVaultAmount = common.token.parseTokenAccountData(
+            (await program.provider.connection.getAccountInfo(escrowVault)).data
+        ).amount;
+
+        const oldRecipientTokenAmount = common.token.parseTokenAccountData(
+            (await program.provider.connection.getAccountInfo(recipientToken)).data
+        ).amount;
+
+        await program.rpc.withdrawAll({
+            accounts: {
+                signer: granter.publicKey,
+                recipientToken: recipientToken,
+                vesting: vesting,
+                config: config,
+                escrowVault: escrowVault,
+                mint: mint,
+                tokenProgram: TOKEN_PROGRAM_ID,
+                clock: anchor.web3.SYSVAR_CLOCK_PUBKEY
+            },
+            signers: [granter.payer]
+        });
+
+        const newRecipientTokenAmount = common.token.parseTokenAccountData(
+            (await program.provider.connection.getAccountInfo(recipientToken)).data
+        ).amount;
+
+        const vestingAccount = await program.account.vesting.fetch(vesting);
+
+        // Verify.
+        assert.ok(vestingAccount.remainingAmount.eqn(0));
+        assert.ok(
+            new BN(oldEscrowVaultAmount).eq(new BN(newRecipientTokenAmount - oldRecipientTokenAmount))
+        );
+    });
+
+    it("Cancel part-way", async () => {
+
+        console.log(`Cancel part-way: `);
+
+        const cancelVestingId = vestingId + 9;
+
+        const [cancelVesting, cancelVestingBump] = await PublicKey.findProgramAddress(
+            [
+                Buffer.from(String(cancelVestingId)),
+                recipient.toBuffer(),
+                mint.toBuffer()
+            ],
+            program.programId
+        );
+
+        const [cancelEscrowVault, cancelEscrowVaultBump] = await PublicKey.findProgramAddress(
+            [cancelVesting.toBuffer()],
+            program.programId
+        );
+
+        // Started 10 seconds ago and unlocks every second, so a part of it is vested on cancel.
+        const now = Math.floor(+new Date() / 1000);
+        let vesting_name = nacl.util.decodeUTF8("DaoLaunch_Cancel");
+        let investor_wallet_address = nacl.util.decodeUTF8("55YsfAvxUi2RkGye5AS3hH6kvoz3Bf1hoQaoX4VVKaF7");
+        await program.rpc.createVesting(
+            depositedAmount,
+            cancelEscrowVaultBump,
+            cancelVestingBump,
+            new BN(cancelVestingId),
+            vesting_name,
+            investor_wallet_address,
+            new BN(now - 10),
+            new BN(now + 1000),
+            new BN(1),
+            new BN(0),
+            new BN(0),
+            new BN(0),
+            true,
+            { authority: {} },
+            { anyone: {} },
+            PublicKey.default,
+            { seconds: {} }, {
+            accounts: {
+                signer: granter.publicKey,
+                paymentVault: paymentVault,
+                granterToken: paymentVault,
+                config: config,
+                recipient: recipient,
+                recipientToken: recipientToken,
+                vesting: cancelVesting,
+                escrowVault: cancelEscrowVault,
+                mint: mint,
+                tokenProgram: TOKEN_PROGRAM_ID,
+                associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+                systemProgram: SystemProgram.programId,
+                clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+                rent: SYSVAR_RENT_PUBKEY
+            },
+            signers: [granter.payer]
+        });
+
+        const tokenAmount = async (account) => new BN(common.token.parseTokenAccountData(
+            (await program.provider.connection.getAccountInfo(account)).data
+        ).amount);
+
+        const oldRecipientTokenAmount = await tokenAmount(recipientToken);
+        const oldPaymentVaultAmount = await tokenAmount(paymentVault);
+
+        await program.rpc.cancel({
+            accounts: {
+                signer: granter.publicKey,
+                payer: granter.publicKey,
+                granterToken: paymentVault,
+                config: config,
+                vesting: cancelVesting,
+                recipientToken: recipientToken,
+                escrowVault: cancelEscrowVault,
+                mint: mint,
+                tokenProgram: TOKEN_PROGRAM_ID,
+                clock: anchor.web3.SYSVAR_CLOCK_PUBKEY
+            },
+            signers: [granter.payer]
+        });
+
+        const settled = (await tokenAmount(recipientToken)).sub(oldRecipientTokenAmount);
+        const returned = (await tokenAmount(paymentVault)).sub(oldPaymentVaultAmount);
+
+        console.log(`settled: ${settled.toString()} returned: ${returned.toString()}`);
+
+        // Verify the vested part went to the recipient and the rest back to the payment vault.
+        assert.ok(settled.gtn(0));
+        assert.ok(settled.lt(depositedAmount));
+        assert.ok(settled.add(returned).eq(depositedAmount));
+        assert.ok((await program.provider.connection.getAccountInfo(cancelEscrowVault)) === null);
+    });
+
+    it("Create milestone vesting and release", async () => {
+
+        console.log(`Create milestone vesting and release: `);
+
+        const milestoneVestingId = vestingId + 1;
+
+        const [milestoneVesting, milestoneVestingBump] = await PublicKey.findProgramAddress(
+            [
+                Buffer.from(String(milestoneVestingId)),
+                recipient.toBuffer(),
+                mint.toBuffer()
+            ],
+            program.programId
+        );
+
+        const [milestoneEscrowVault, milestoneEscrowVaultBump] = await PublicKey.findProgramAddress(
+            [milestoneVesting.toBuffer()],
+            program.programId
+        );
+
+        let vesting_name = nacl.util.decodeUTF8("DaoLaunch_Milestone");
+        let investor_wallet_address = nacl.util.decodeUTF8("55YsfAvxUi2RkGye5AS3hH6kvoz3Bf1hoQaoX4VVKaF7");
+        await program.rpc.createMilestoneVesting(
+            depositedAmount,
+            milestoneEscrowVaultBump,
+            milestoneVestingBump,
+            new BN(milestoneVestingId),
+            vesting_name,
+            investor_wallet_address,
+            [new BN(2500), new BN(7500)],
+            PublicKey.default,
+            { authority: {} },
+            { anyone: {} },
+            PublicKey.default, {
+            accounts: {
+                signer: granter.publicKey,
+                paymentVault: paymentVault,
+                granterToken: paymentVault,
+                config: config,
+                recipient: recipient,
+                recipientToken: recipientToken,
+                vesting: milestoneVesting,
+                escrowVault: milestoneEscrowVault,
+                mint: mint,
+                tokenProgram: TOKEN_PROGRAM_ID,
+                associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+                systemProgram: SystemProgram.programId,
+                clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+                rent: SYSVAR_RENT_PUBKEY
+            },
+            signers: [granter.payer]
+        });
+
+        await program.rpc.releaseMilestone(
+            0, {
+            accounts: {
+                signer: granter.publicKey,
+                config: config,
+                vesting: milestoneVesting,
+                clock: anchor.web3.SYSVAR_CLOCK_PUBKEY
+            },
+            signers: [granter.payer]
+        });
+
+        const vestingAccount = await program.account.vesting.fetch(milestoneVesting);
+
+        // Verify.
+        assert.ok(!vestingAccount.milestones[0].releasedTs.eqn(0));
+        assert.ok(vestingAccount.milestones[1].releasedTs.eqn(0));
+        assert.ok(
+            vestingAccount.milestones[0].amount.add(vestingAccount.milestones[1].amount).eq(depositedAmount)
+        );
+    });
+
+    it("Create piecewise vesting", async () => {
+
+        console.log(`Create piecewise vesting: `);
+
+        const piecewiseVestingId = vestingId + 2;
+
+        const [piecewiseVesting, piecewiseVestingBump] = await PublicKey.findProgramAddress(
+            [
+                Buffer.from(String(piecewiseVestingId)),
+                recipient.toBuffer(),
+                mint.toBuffer()
+            ],
+            program.programId
+        );
+
+        const [piecewiseEscrowVault, piecewiseEscrowVaultBump] = await PublicKey.findProgramAddress(
+            [piecewiseVesting.toBuffer()],
+            program.programId
+        );
+
+        // 10% at TGE, 6 month cliff, then 15% per quarter and the rest at the end.
+        const month = 30 * 86400;
+        const breakpoints = [
+            { ts: start, rate: new BN(1000) },
+            { ts: start.addn(6 * month), rate: new BN(2500) },
+            { ts: start.addn(9 * month), rate: new BN(4000) },
+            { ts: start.addn(12 * month), rate: new BN(10000) },
+        ];
+
+        let vesting_name = nacl.util.decodeUTF8("DaoLaunch_Piecewise");
+        let investor_wallet_address = nacl.util.decodeUTF8("55YsfAvxUi2RkGye5AS3hH6kvoz3Bf1hoQaoX4VVKaF7");
+        await program.rpc.createPiecewiseVesting(
+            depositedAmount,
+            piecewiseEscrowVaultBump,
+            piecewiseVestingBump,
+            new BN(piecewiseVestingId),
+            vesting_name,
+            investor_wallet_address,
+            breakpoints,
+            { step: {} },
+            { authority: {} },
+            { anyone: {} },
+            PublicKey.default, {
+            accounts: {
+                signer: granter.publicKey,
+                paymentVault: paymentVault,
+                granterToken: paymentVault,
+                config: config,
+                recipient: recipient,
+                recipientToken: recipientToken,
+                vesting: piecewiseVesting,
+                escrowVault: piecewiseEscrowVault,
+                mint: mint,
+                tokenProgram: TOKEN_PROGRAM_ID,
+                associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+                systemProgram: SystemProgram.programId,
+                clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+                rent: SYSVAR_RENT_PUBKEY
+            },
+            signers: [granter.payer]
+        });
+
+        const vestingAccount = await program.account.vesting.fetch(piecewiseVesting);
+
+        // Verify.
+        assert.equal(vestingAccount.breakpoints.length, breakpoints.length);
+        assert.ok(vestingAccount.startTs.eq(start));
+        assert.ok(vestingAccount.endTs.eq(breakpoints[breakpoints.length - 1].ts));
+    });
+
+    it("Top up vesting", async () => {
+
+        console.log(`Top up vesting: `);
+
+        const amount = new BN(10 * LAMPORTS_PER_SOL);
+        const oldVestingAccount = await program.account.vesting.fetch(vesting);
+
+        await program.rpc.topUpVesting(
+            amount,
+            false, {
+            accounts: {
+                signer: granter.publicKey,
+                paymentVault: paymentVault,
+                config: config,
+                vesting: vesting,
+                escrowVault: escrowVault,
+                tokenProgram: TOKEN_PROGRAM_ID,
+                clock: anchor.web3.SYSVAR_CLOCK_PUBKEY
+            },
+            signers: [granter.payer]
+        });
+
+        const vestingAccount = await program.account.vesting.fetch(vesting);
+
+        // Verify.
+        assert.ok(vestingAccount.totalAmount.eq(oldVestingAccount.totalAmount.add(amount)));
+        assert.ok(vestingAccount.remainingAmount.eq(oldVestingAccount.remainingAmount.add(amount)));
+    });
+
+    it("Amend schedule", async () => {
+
+        console.log(`Amend schedule: `);
+
+        const amendVestingId = vestingId + 7;
+
+        // The granter is the recipient too, both have to sign the amendment.
+        const granterToken = await Token.getAssociatedTokenAddress(
+            ASSOCIATED_TOKEN_PROGRAM_ID,
+            TOKEN_PROGRAM_ID,
+            mint,
+            granter.publicKey
+        );
+
+        const [amendVesting, amendVestingBump] = await PublicKey.findProgramAddress(
+            [
+                Buffer.from(String(amendVestingId)),
+                granter.publicKey.toBuffer(),
+                mint.toBuffer()
+            ],
+            program.programId
+        );
+
+        const [amendEscrowVault, amendEscrowVaultBump] = await PublicKey.findProgramAddress(
+            [amendVesting.toBuffer()],
+            program.programId
+        );
+
+        // A schedule still running, so the amendment has something left to reschedule.
+        const amendStart = new BN(Math.floor(Date.now() / 1000));
+        const amendEnd = amendStart.add(period.muln(10));
+        const amount = new BN(10 * LAMPORTS_PER_SOL);
+
+        let vesting_name = nacl.util.decodeUTF8("DaoLaunch_Amend");
+        let investor_wallet_address = nacl.util.decodeUTF8("55YsfAvxUi2RkGye5AS3hH6kvoz3Bf1hoQaoX4VVKaF7");
+        await program.rpc.createVesting(
+            amount,
+            amendEscrowVaultBump,
+            amendVestingBump,
+            new BN(amendVestingId),
+            vesting_name,
+            investor_wallet_address,
+            amendStart,
+            amendEnd,
+            period,
+            cliff,
+            new BN(0),
+            new BN(0),
+            true,
+            { authority: {} },
+            { recipient: {} },
+            PublicKey.default,
+            { seconds: {} }, {
+            accounts: {
+                signer: granter.publicKey,
+                paymentVault: paymentVault,
+                granterToken: paymentVault,
+                config: config,
+                recipient: granter.publicKey,
+                recipientToken: granterToken,
+                vesting: amendVesting,
+                escrowVault: amendEscrowVault,
+                mint: mint,
+                tokenProgram: TOKEN_PROGRAM_ID,
+                associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+                systemProgram: SystemProgram.programId,
+                clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+                rent: SYSVAR_RENT_PUBKEY
+            },
+            signers: [granter.payer]
+        });
+
+        // Double the schedule and release 10% at TGE.
+        const newEnd = amendStart.add(period.muln(20));
+        await program.rpc.amendSchedule(
+            {
+                endTs: newEnd,
+                period: period,
+                cliff: new BN(0),
+                cliffReleaseRate: new BN(0),
+                tgeReleaseRate: new BN(1000),
+            }, {
+            accounts: {
+                authority: granter.publicKey,
+                recipient: granter.publicKey,
+                config: config,
+                vesting: amendVesting,
+                clock: anchor.web3.SYSVAR_CLOCK_PUBKEY
+            },
+            signers: [granter.payer]
+        });
+
+        const vestingAccount = await program.account.vesting.fetch(amendVesting);
+
+        // Verify the amended terms and amounts are stored, the amount is untouched.
+        assert.ok(vestingAccount.endTs.eq(newEnd));
+        assert.ok(vestingAccount.tgeReleaseRate.eqn(1000));
+        assert.ok(vestingAccount.tgeAmount.eq(amount.divn(10)));
+        assert.ok(vestingAccount.totalAmount.eq(amount));
+        assert.ok(vestingAccount.remainingAmount.eq(amount));
+    });
+
+    it("Create vestings batch", async () => {
+
+        console.log(`Create vestings batch: `);
+
+        // A full batch of MAX_BATCH_ENTRIES vestings of the recipient, each entry passes its
+        // recipient, recipient token, vesting and escrow vault accounts in order.
+        const entries = [];
+        const remainingAccounts = [];
+        for (const batchVestingId of [vestingId + 3, vestingId + 4, vestingId + 10]) {
+            const [batchVesting, batchVestingBump] = await PublicKey.findProgramAddress(
+                [
+                    Buffer.from(String(batchVestingId)),
+                    recipient.toBuffer(),
+                    mint.toBuffer()
+                ],
+                program.programId
+            );
+
+            const [batchEscrowVault, batchEscrowVaultBump] = await PublicKey.findProgramAddress(
+                [batchVesting.toBuffer()],
+                program.programId
+            );
+
+            entries.push({
+                recipient: recipient,
+                totalAmount: depositedAmount,
+                vestingId: new BN(batchVestingId),
+                vestingBump: batchVestingBump,
+                escrowVaultBump: batchEscrowVaultBump,
+                startTs: start,
+                periodUnit: { seconds: {} },
+                terms: {
+                    endTs: end,
+                    period: period,
+                    cliff: cliff,
+                    cliffReleaseRate: new BN(0),
+                    tgeReleaseRate: new BN(2000),
+                },
+            });
+            remainingAccounts.push(
+                { pubkey: recipient, isWritable: false, isSigner: false },
+                { pubkey: recipientToken, isWritable: true, isSigner: false },
+                { pubkey: batchVesting, isWritable: true, isSigner: false },
+                { pubkey: batchEscrowVault, isWritable: true, isSigner: false },
+            );
+        }
+
+        await program.rpc.createVestingsBatch(
+            entries,
+            true,
+            { authority: {} },
+            { anyone: {} }, {
+            accounts: {
+                signer: granter.publicKey,
+                paymentVault: paymentVault,
+                config: config,
+                mint: mint,
+                tokenProgram: TOKEN_PROGRAM_ID,
+                associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+                systemProgram: SystemProgram.programId,
+                clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+                rent: SYSVAR_RENT_PUBKEY
+            },
+            remainingAccounts: remainingAccounts,
+            signers: [granter.payer]
+        });
+
+        // Verify.
+        for (let i = 0; i < entries.length; i++) {
+            const vestingAccount = await program.account.vesting.fetch(remainingAccounts[i * 4 + 2].pubkey);
+            assert.ok(vestingAccount.vestingId.eq(entries[i].vestingId));
+            assert.ok(vestingAccount.totalAmount.eq(depositedAmount));
+            assert.ok(vestingAccount.recipient.equals(recipient));
+        }
+    });
+
+    it("Create merkle pool", async () => {
+
+        console.log(`Create merkle pool: `);
+
+        const poolId = 1;
+
+        const [merklePool, merklePoolBump] = await PublicKey.findProgramAddress(
+            [
+                Buffer.from("merkle_pool"),
+                Buffer.from(String(poolId)),
+                mint.toBuffer()
+            ],
+            program.programId
+        );
+
+        const [poolEscrowVault, poolEscrowVaultBump] = await PublicKey.findProgramAddress(
+            [merklePool.toBuffer()],
+            program.programId
+        );
+
+        // The root is built off-chain from the leaves, recipients claim with their proofs.
+        const merkleRoot = Array.from(nacl.randomBytes(32));
+        const claimDeadline = new BN(Math.floor(+new Date() / 1000) + 30 * 86400);
+
+        await program.rpc.createMerklePool(
+            merklePoolBump,
+            poolEscrowVaultBump,
+            new BN(poolId),
+            merkleRoot,
+            depositedAmount,
+            claimDeadline,
+            { authority: {} },
+            { recipient: {} }, {
+            accounts: {
+                signer: granter.publicKey,
+                paymentVault: paymentVault,
+                config: config,
+                merklePool: merklePool,
+                escrowVault: poolEscrowVault,
+                mint: mint,
+                tokenProgram: TOKEN_PROGRAM_ID,
+                systemProgram: SystemProgram.programId,
+                clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+                rent: SYSVAR_RENT_PUBKEY
+            },
+            signers: [granter.payer]
+        });
+
+        const merklePoolAccount = await program.account.merklePool.fetch(merklePool);
+
+        // Verify.
+        assert.deepEqual(merklePoolAccount.merkleRoot, merkleRoot);
+        assert.ok(merklePoolAccount.totalAmount.eq(depositedAmount));
+        assert.ok(merklePoolAccount.claimedAmount.eqn(0));
+        assert.ok(merklePoolAccount.claimDeadline.eq(claimDeadline));
+    });
+
+    it("Claim merkle vesting once", async () => {
+
+        console.log(`Claim merkle vesting once: `);
+
+        const poolId = 2;
+
+        // The claimer signs the claim and pays its rent.
+        const claimer = Keypair.generate();
+        await provider.connection.confirmTransaction(
+            await provider.connection.requestAirdrop(claimer.publicKey, LAMPORTS_PER_SOL)
+        );
+
+        const claimerToken = await Token.getAssociatedTokenAddress(
+            ASSOCIATED_TOKEN_PROGRAM_ID,
+            TOKEN_PROGRAM_ID,
+            mint,
+            claimer.publicKey
+        );
+
+        const [merklePool, merklePoolBump] = await PublicKey.findProgramAddress(
+            [
+                Buffer.from("merkle_pool"),
+                Buffer.from(String(poolId)),
+                mint.toBuffer()
+            ],
+            program.programId
+        );
+
+        const [poolEscrowVault, poolEscrowVaultBump] = await PublicKey.findProgramAddress(
+            [merklePool.toBuffer()],
+            program.programId
+        );
+
+        // A single leaf tree, its root is the leaf hash. The pool holds twice the leaf, so only
+        // the claim receipt stops a second claim.
+        const now = Math.floor(+new Date() / 1000);
+        const leaf = {
+            index: new BN(0),
+            recipient: claimer.publicKey,
+            totalAmount: depositedAmount.divn(2),
+            startTs: new BN(now - 10),
+            periodUnit: { seconds: {} },
+            terms: {
+                endTs: new BN(now + 1000),
+                period: new BN(1),
+                cliff: new BN(0),
+                cliffReleaseRate: new BN(0),
+                tgeReleaseRate: new BN(0),
+            },
+        };
+        const merkleRoot = keccak_256.array(
+            Buffer.concat([Buffer.from([0]), program.coder.types.encode("MerkleLeaf", leaf)])
+        );
+
+        await program.rpc.createMerklePool(
+            merklePoolBump,
+            poolEscrowVaultBump,
+            new BN(poolId),
+            merkleRoot,
+            leaf.totalAmount.muln(2),
+            new BN(now + 30 * 86400),
+            { authority: {} },
+            { recipient: {} }, {
+            accounts: {
+                signer: granter.publicKey,
+                paymentVault: paymentVault,
+                config: config,
+                merklePool: merklePool,
+                escrowVault: poolEscrowVault,
+                mint: mint,
+                tokenProgram: TOKEN_PROGRAM_ID,
+                systemProgram: SystemProgram.programId,
+                clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+                rent: SYSVAR_RENT_PUBKEY
+            },
+            signers: [granter.payer]
+        });
+
+        const [claimVesting, claimVestingBump] = await PublicKey.findProgramAddress(
+            [merklePool.toBuffer(), Buffer.from(String(leaf.index))],
+            program.programId
+        );
+
+        const [claimEscrowVault, claimEscrowVaultBump] = await PublicKey.findProgramAddress(
+            [claimVesting.toBuffer()],
+            program.programId
+        );
+
+        const [claimReceipt, claimReceiptBump] = await PublicKey.findProgramAddress(
+            [Buffer.from("claim_receipt"), merklePool.toBuffer(), Buffer.from(String(leaf.index))],
+            program.programId
+        );
+
+        const claim = () => program.rpc.claimMerkleVesting(
+            claimVestingBump,
+            claimEscrowVaultBump,
+            claimReceiptBump,
+            leaf,
+            [], {
+            accounts: {
+                recipient: claimer.publicKey,
+                recipientToken: claimerToken,
+                config: config,
+                merklePool: merklePool,
+                poolEscrowVault: poolEscrowVault,
+                claimReceipt: claimReceipt,
+                vesting: claimVesting,
+                escrowVault: claimEscrowVault,
+                mint: mint,
+                tokenProgram: TOKEN_PROGRAM_ID,
+                associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+                systemProgram: SystemProgram.programId,
+                clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+                rent: SYSVAR_RENT_PUBKEY
+            },
+            signers: [claimer]
+        });
+
+        await claim();
+
+        const claimReceiptAccount = await program.account.claimReceipt.fetch(claimReceipt);
+        assert.ok(claimReceiptAccount.vesting.equals(claimVesting));
+
+        // Cancel the claimed vesting, which closes it and its escrow vault.
+        await program.rpc.cancel({
+            accounts: {
+                signer: granter.publicKey,
+                payer: claimer.publicKey,
+                granterToken: paymentVault,
+                config: config,
+                vesting: claimVesting,
+                recipientToken: claimerToken,
+                escrowVault: claimEscrowVault,
+                mint: mint,
+                tokenProgram: TOKEN_PROGRAM_ID,
+                clock: anchor.web3.SYSVAR_CLOCK_PUBKEY
+            },
+            signers: [granter.payer]
+        });
+        assert.ok((await program.provider.connection.getAccountInfo(claimVesting)) === null);
+
+        // The receipt is still there, so the leaf can not be claimed again.
+        try {
+            await claim();
+            assert.fail("claim merkle vesting should fail once the leaf is claimed");
+        } catch (err) {
+            assert.notEqual(err.message, "claim merkle vesting should fail once the leaf is claimed");
+        }
+
+        const merklePoolAccount = await program.account.merklePool.fetch(merklePool);
+        assert.ok(merklePoolAccount.claimedAmount.eq(leaf.totalAmount));
+    });
+
+    it("Create vesting funded by the signer", async () => {
+
+        console.log(`Create vesting funded by the signer: `);
+
+        const signerVestingId = vestingId + 5;
+
+        const granterToken = await Token.getAssociatedTokenAddress(
+            ASSOCIATED_TOKEN_PROGRAM_ID,
+            TOKEN_PROGRAM_ID,
+            mint,
+            granter.publicKey
+        );
+
+        // Move the tokens to the signer's own token account first.
+        await program.rpc.withdrawPaymentVault(
+            depositedAmount, {
+            accounts: {
+                signer: granter.publicKey,
+                destinationToken: granterToken,
+                paymentVault: paymentVault,
+                config: config,
+                mint: mint,
+                tokenProgram: TOKEN_PROGRAM_ID,
+            },
+            signers: [granter.payer]
+        });
+
+        const [signerVesting, signerVestingBump] = await PublicKey.findProgramAddress(
+            [
+                Buffer.from(String(signerVestingId)),
+                recipient.toBuffer(),
+                mint.toBuffer()
+            ],
+            program.programId
+        );
+
+        const [signerEscrowVault, signerEscrowVaultBump] = await PublicKey.findProgramAddress(
+            [signerVesting.toBuffer()],
+            program.programId
+        );
+
+        let vesting_name = nacl.util.decodeUTF8("DaoLaunch_Signer");
+        let investor_wallet_address = nacl.util.decodeUTF8("55YsfAvxUi2RkGye5AS3hH6kvoz3Bf1hoQaoX4VVKaF7");
+        await program.rpc.createVesting(
+            depositedAmount,
+            signerEscrowVaultBump,
+            signerVestingBump,
+            new BN(signerVestingId),
+            vesting_name,
+            investor_wallet_address,
+            start,
+            end,
+            period,
+            cliff,
+            new BN(0),
+            new BN(2000),
+            true,
+            { authority: {} },
+            { anyone: {} },
+            PublicKey.default,
+            { seconds: {} }, {
+            accounts: {
+                signer: granter.publicKey,
+                paymentVault: paymentVault,
+                granterToken: granterToken,
+                config: config,
+                recipient: recipient,
+                recipientToken: recipientToken,
+                vesting: signerVesting,
+                escrowVault: signerEscrowVault,
+                mint: mint,
+                tokenProgram: TOKEN_PROGRAM_ID,
+                associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+                systemProgram: SystemProgram.programId,
+                clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+                rent: SYSVAR_RENT_PUBKEY
+            },
+            signers: [granter.payer]
+        });
+
+        const vestingAccount = await program.account.vesting.fetch(signerVesting);
+        const escrowVaultAmount = common.token.parseTokenAccountData(
+            (await program.provider.connection.getAccountInfo(signerEscrowVault)).data
+        ).amount;
+
+        // Verify the real source is recorded, cancel returns the unvested tokens to it.
+        assert.ok(vestingAccount.granter.equals(granter.publicKey));
+        assert.ok(vestingAccount.granterToken.equals(granterToken));
+        assert.ok(new BN(escrowVaultAmount).eq(depositedAmount));
+    });
+
+    it("Create self-funded vesting and withdraw", async () => {
+
+        console.log(`Create self-funded vesting and withdraw: `);
+
+        const selfFundedVestingId = vestingId + 6;
+
+        const granterToken = await Token.getAssociatedTokenAddress(
+            ASSOCIATED_TOKEN_PROGRAM_ID,
+            TOKEN_PROGRAM_ID,
+            mint,
+            granter.publicKey
+        );
+
+        // Move the tokens to the signer's own token account first.
+        await program.rpc.withdrawPaymentVault(
+            depositedAmount, {
+            accounts: {
+                signer: granter.publicKey,
+                destinationToken: granterToken,
+                paymentVault: paymentVault,
+                config: config,
+                mint: mint,
+                tokenProgram: TOKEN_PROGRAM_ID,
+            },
+            signers: [granter.payer]
+        });
+
+        // The vesting is keyed by the granter too, so ids never collide with other granters.
+        const [selfFundedVesting, selfFundedVestingBump] = await PublicKey.findProgramAddress(
+            [
+                Buffer.from(String(selfFundedVestingId)),
+                recipient.toBuffer(),
+                mint.toBuffer(),
+                granter.publicKey.toBuffer()
+            ],
+            program.programId
+        );
+
+        const [selfFundedEscrowVault, selfFundedEscrowVaultBump] = await PublicKey.findProgramAddress(
+            [selfFundedVesting.toBuffer()],
+            program.programId
+        );
+
+        let vesting_name = nacl.util.decodeUTF8("DaoLaunch_SelfFunded");
+        let investor_wallet_address = nacl.util.decodeUTF8("55YsfAvxUi2RkGye5AS3hH6kvoz3Bf1hoQaoX4VVKaF7");
+        await program.rpc.createSelfFundedVesting(
+            depositedAmount,
+            selfFundedEscrowVaultBump,
+            selfFundedVestingBump,
+            new BN(selfFundedVestingId),
+            vesting_name,
+            investor_wallet_address,
+            start,
+            end,
+            period,
+            cliff,
+            new BN(0),
+            new BN(2000),
+            true,
+            { authority: {} },
+            { anyone: {} },
+            PublicKey.default,
+            { seconds: {} }, {
+            accounts: {
+                signer: granter.publicKey,
+                granterToken: granterToken,
+                recipient: recipient,
+                recipientToken: recipientToken,
+                vesting: selfFundedVesting,
+                escrowVault: selfFundedEscrowVault,
+                mint: mint,
+                tokenProgram: TOKEN_PROGRAM_ID,
+                associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+                systemProgram: SystemProgram.programId,
+                clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+                rent: SYSVAR_RENT_PUBKEY
+            },
+            signers: [granter.payer]
+        });
+
+        let vestingAccount = await program.account.vesting.fetch(selfFundedVesting);
+        assert.ok(vestingAccount.selfFunded);
+        assert.ok(vestingAccount.granter.equals(granter.publicKey));
+        assert.ok(vestingAccount.granterToken.equals(granterToken));
+
+        // The schedule has ended, everything is available without the config.
+        await program.rpc.withdrawSelfFunded(
+            new BN("18446744073709551615"), {
+            accounts: {
+                signer: granter.publicKey,
+                recipientToken: recipientToken,
+                vesting: selfFundedVesting,
+                escrowVault: selfFundedEscrowVault,
+                mint: mint,
+                tokenProgram: TOKEN_PROGRAM_ID,
+                clock: anchor.web3.SYSVAR_CLOCK_PUBKEY
+            },
+            signers: [granter.payer]
+        });
+
+        vestingAccount = await program.account.vesting.fetch(selfFundedVesting);
+        assert.ok(vestingAccount.remainingAmount.eqn(0));
+        assert.ok(vestingAccount.withdrawnAmount.eq(depositedAmount));
+    });
+
+    it("Transfer self-funded vesting", async () => {
+
+        console.log(`Transfer self-funded vesting: `);
+
+        const selfFundedVestingId = vestingId + 8;
+        const amount = new BN(10 * LAMPORTS_PER_SOL);
+        const delegate = Keypair.generate();
+
+        const granterToken = await Token.getAssociatedTokenAddress(
+            ASSOCIATED_TOKEN_PROGRAM_ID,
+            TOKEN_PROGRAM_ID,
+            mint,
+            granter.publicKey
+        );
+
+        // Move the tokens to the signer's own token account first.
+        await program.rpc.withdrawPaymentVault(
+            amount, {
+            accounts: {
+                signer: granter.publicKey,
+                destinationToken: granterToken,
+                paymentVault: paymentVault,
+                config: config,
+                mint: mint,
+                tokenProgram: TOKEN_PROGRAM_ID,
+            },
+            signers: [granter.payer]
+        });
+
+        // The granter is the recipient too, the current recipient has to sign the transfer.
+        const [selfFundedVesting, selfFundedVestingBump] = await PublicKey.findProgramAddress(
+            [
+                Buffer.from(String(selfFundedVestingId)),
+                granter.publicKey.toBuffer(),
+                mint.toBuffer(),
+                granter.publicKey.toBuffer()
+            ],
+            program.programId
+        );
+
+        const [selfFundedEscrowVault, selfFundedEscrowVaultBump] = await PublicKey.findProgramAddress(
+            [selfFundedVesting.toBuffer()],
+            program.programId
+        );
+
+        let vesting_name = nacl.util.decodeUTF8("DaoLaunch_Transfer");
+        let investor_wallet_address = nacl.util.decodeUTF8("55YsfAvxUi2RkGye5AS3hH6kvoz3Bf1hoQaoX4VVKaF7");
+        await program.rpc.createSelfFundedVesting(
+            amount,
+            selfFundedEscrowVaultBump,
+            selfFundedVestingBump,
+            new BN(selfFundedVestingId),
+            vesting_name,
+            investor_wallet_address,
+            start,
+            end,
+            period,
+            cliff,
+            new BN(0),
+            new BN(2000),
+            true,
+            { authority: {} },
+            { recipientOrDelegate: {} },
+            delegate.publicKey,
+            { seconds: {} }, {
+            accounts: {
+                signer: granter.publicKey,
+                granterToken: granterToken,
+                recipient: granter.publicKey,
+                recipientToken: granterToken,
+                vesting: selfFundedVesting,
+                escrowVault: selfFundedEscrowVault,
+                mint: mint,
+                tokenProgram: TOKEN_PROGRAM_ID,
+                associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+                systemProgram: SystemProgram.programId,
+                clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+                rent: SYSVAR_RENT_PUBKEY
+            },
+            signers: [granter.payer]
+        });
+
+        // The transfer approval of the config does not apply to self-funded vestings.
+        await program.rpc.setTransferApproval(
+            true, {
+            accounts: {
+                signer: granter.publicKey,
+                config: config,
+            },
+            signers: [granter.payer]
+        });
+
+        await program.rpc.transferVestingSelfFunded({
+            accounts: {
+                signer: granter.publicKey,
+                vesting: selfFundedVesting,
+                newRecipient: recipient,
+                newRecipientToken: recipientToken,
+                mint: mint,
+                tokenProgram: TOKEN_PROGRAM_ID,
+                associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+                systemProgram: SystemProgram.programId,
+                rent: SYSVAR_RENT_PUBKEY
+            },
+            signers: [granter.payer]
+        });
+
+        await program.rpc.setTransferApproval(
+            false, {
+            accounts: {
+                signer: granter.publicKey,
+                config: config,
+            },
+            signers: [granter.payer]
+        });
+
+        const vestingAccount = await program.account.vesting.fetch(selfFundedVesting);
+
+        // Verify the new recipient, the delegate of the previous recipient is dropped.
+        assert.ok(vestingAccount.recipient.equals(recipient));
+        assert.ok(vestingAccount.recipientToken.equals(recipientToken));
+        assert.ok(vestingAccount.withdrawDelegate.equals(PublicKey.default));
+        assert.ok(vestingAccount.withdrawAuthority.recipient !== undefined);
+    });
+
 //     it("Cancel", async () => {
 
 //         await sleep(12000);
@@ -474,12 +1721,15 @@ ${vesting_data.escrowVault.toBase58()}
 //         const tx = await program.rpc.cancel({
 //             accounts: {
 //                 signer: granter.publicKey,
-//                 paymentVault: paymentVault,
+//                 payer: granter.publicKey,
+//                 granterToken: paymentVault,
 //                 config: config,
 //                 vesting: vesting,
+//                 recipientToken: recipientToken,
 //                 escrowVault: escrowVault,
 //                 mint: mint,
-//                 tokenProgram: TOKEN_PROGRAM_ID
+//                 tokenProgram: TOKEN_PROGRAM_ID,
+//                 clock: anchor.web3.SYSVAR_CLOCK_PUBKEY
 //             },
 //             signers: [granter.payer]
 //         });